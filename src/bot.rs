@@ -0,0 +1,143 @@
+use poise::serenity_prelude::{GatewayIntents, Http};
+use std::process;
+use std::sync::Arc;
+use tracing::error;
+
+use crate::commands::daily::{name_of_the_day, start_daily_names};
+use crate::commands::exclusions::exclusions;
+use crate::commands::family::family;
+use crate::commands::favorites::favorites;
+use crate::commands::history::name_history;
+use crate::commands::life::another_life;
+use crate::commands::names::{about_name, debug_name, debug_usages, help_rnc, name, name_prefix};
+use crate::commands::nickname::rename;
+use crate::commands::no_repeat::no_repeat;
+use crate::commands::prefs::rnc_prefs;
+use crate::commands::roster::roster;
+use crate::commands::roulette::{nickname_roulette, resume_roulettes};
+use crate::commands::settings::{apply_ephemeral, check_channel, guild_prefix, rnc_settings};
+use crate::config::{Config, Mode};
+use crate::http;
+use crate::interactions::Interactions;
+use crate::resources::types::Data;
+use crate::storage::Storage;
+
+/// Run the Discord bot until it's stopped.
+pub async fn run() {
+    // Settings come from `randomnamecord.toml` (or the file named by
+    // `RNC_CONFIG`), overridden by environment variables, which may be set in
+    // `./.env`. See the README for the available settings.
+    let config = match Config::load(true, true) {
+        Ok(config) => config.install(),
+        Err(e) => {
+            eprintln!("Invalid configuration:\n{}", e);
+            process::exit(1);
+        }
+    };
+
+    tracing_subscriber::fmt()
+        .with_max_level(config.log_level)
+        .init();
+
+    let storage = Storage::open(&config.database_path).expect("Failed to open the database");
+
+    if config.mode == Mode::Interactions {
+        serve_interactions(config, storage).await;
+        return;
+    }
+
+    // Not configurable: prefix commands need the message content, and the
+    // rest of the bot only the non-privileged intents. See the README.
+    let gateway_intents = GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT;
+
+    let framework = poise::Framework::builder()
+        .options(poise::FrameworkOptions {
+            commands: vec![
+                // ~name before /name, as prefix commands are found by
+                // the first command with their name.
+                name_prefix(),
+                name(),
+                debug_name(),
+                debug_usages(),
+                about_name(),
+                family(),
+                another_life(),
+                name_history(),
+                favorites(),
+                roster(),
+                rename(),
+                nickname_roulette(),
+                name_of_the_day(),
+                no_repeat(),
+                exclusions(),
+                rnc_settings(),
+                rnc_prefs(),
+                help_rnc(),
+            ],
+            prefix_options: poise::PrefixFrameworkOptions {
+                dynamic_prefix: Some(|ctx| Box::pin(guild_prefix(ctx))),
+                ..Default::default()
+            },
+            command_check: Some(|ctx| Box::pin(check_channel(ctx))),
+            reply_callback: Some(apply_ephemeral),
+            ..Default::default()
+        })
+        .token(&config.discord_token)
+        .intents(gateway_intents)
+        .client_settings(|client| {
+            client.cache_settings(|cache| cache.max_messages(config.max_cached_messages))
+        })
+        .setup(move |ctx, _ready, framework| {
+            Box::pin(async move {
+                let commands = &framework.options().commands;
+                match config.test_guild {
+                    Some(guild_id) => {
+                        poise::builtins::register_in_guild(ctx, commands, guild_id).await?
+                    }
+                    None => poise::builtins::register_globally(ctx, commands).await?,
+                }
+                let data = Data::new(storage);
+                resume_roulettes(ctx.http.clone(), data.storage.clone())?;
+                start_daily_names(ctx.http.clone(), data.storage.clone());
+                Ok(data)
+            })
+        });
+
+    if let Some(address) = config.http_listen {
+        tokio::spawn(async move {
+            if let Err(e) = http::serve(address, None).await {
+                error!("At serving the HTTP API on {}: {}", address, e);
+            }
+        });
+    }
+
+    framework.run().await.unwrap();
+}
+
+/// Answer slash commands on the interactions endpoint instead of over a
+/// gateway connection, with the commands that work without one.
+async fn serve_interactions(config: &Config, storage: Storage) {
+    let http = Arc::new(Http::new(&config.discord_token));
+    let application = http
+        .get_current_application_info()
+        .await
+        .expect("Failed to get the application's info");
+    http.set_application_id(application.id.0);
+
+    let commands = [name(), about_name()];
+    let registered = match config.test_guild {
+        Some(guild_id) => poise::builtins::register_in_guild(&http, &commands, guild_id).await,
+        None => poise::builtins::register_globally(&http, &commands).await,
+    };
+    registered.expect("Failed to register the commands");
+
+    let address = config.http_listen.unwrap();
+    let interactions = Interactions {
+        http,
+        storage: Arc::new(storage),
+        public_key: config.discord_public_key.unwrap(),
+    };
+    if let Err(e) = http::serve(address, Some(Arc::new(interactions))).await {
+        error!("At serving the interactions endpoint on {}: {}", address, e);
+    }
+}
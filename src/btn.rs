@@ -0,0 +1,75 @@
+use std::cell::{Cell, RefCell, RefMut};
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use behindthename::session::Session;
+use behindthename::types::{JsonResponse, RateLimited, RemoteError};
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// A BehindTheName session that spaces out its requests and counts them, so
+/// that retries can be kept within a budget.
+///
+/// The session also carries the random number generator for every local
+/// random choice made while generating, so that a seed makes those choices
+/// repeatable. BehindTheName's own picks are random on its side and can't be
+/// seeded.
+pub(crate) struct BtnSession<'a> {
+    session: Session<'a>,
+    interval: Duration,
+    requests: Cell<usize>,
+    rng: RefCell<ChaCha8Rng>,
+}
+
+/// When the next BehindTheName request may be made, shared by every session
+/// so that the bot and the HTTP API together stay within the key's limits.
+static NEXT_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Wait until a request may be made, spacing requests `interval` apart.
+fn wait_turn(interval: Duration) {
+    let wait = {
+        let mut next_request = NEXT_REQUEST.lock().unwrap();
+        let now = Instant::now();
+        let turn = next_request.map_or(now, |next| next.max(now));
+        *next_request = Some(turn + interval);
+        turn - now
+    };
+    sleep(wait);
+}
+
+/// A seed short enough to type back into a command.
+pub(crate) fn random_seed() -> u64 {
+    thread_rng().gen::<u32>().into()
+}
+
+impl BtnSession<'_> {
+    /// A session whose requests are spaced `interval` apart from any
+    /// session's.
+    pub(crate) fn new(key: &str, interval: Duration, seed: u64) -> BtnSession<'_> {
+        BtnSession {
+            session: Session::new_default(key),
+            interval,
+            requests: Cell::new(0),
+            rng: RefCell::new(ChaCha8Rng::seed_from_u64(seed)),
+        }
+    }
+
+    pub(crate) fn request(
+        &self,
+        req: impl FnOnce(&str) -> String,
+    ) -> RateLimited<'_, JsonResponse, RemoteError> {
+        wait_turn(self.interval);
+        self.requests.set(self.requests.get() + 1);
+        self.session.request(req)
+    }
+
+    /// The number of requests made so far.
+    pub(crate) fn requests(&self) -> usize {
+        self.requests.get()
+    }
+
+    pub(crate) fn rng(&self) -> RefMut<'_, ChaCha8Rng> {
+        self.rng.borrow_mut()
+    }
+}
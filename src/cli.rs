@@ -0,0 +1,238 @@
+//! `randomnamecord-cli`: the bot's name generation, for scripts.
+
+use std::process::ExitCode;
+
+use behindthename::types::Gender;
+use clap::{Parser, Subcommand};
+
+use crate::btn::random_seed;
+use crate::commands::names::{_about, _dbg_name, _name};
+use crate::config::{config, Config};
+use crate::naming::constraints::Constraints;
+use crate::naming::export::{export, NameRecord};
+use crate::naming::filter::Exclusions;
+use crate::naming::format::FormatOptions;
+use crate::repl;
+use crate::resources::types::*;
+use crate::storage::Storage;
+
+const MAX_COUNT: u32 = 100;
+
+/// Random names from BehindTheName.
+///
+/// Reads the BehindTheName API key and defaults from the same config
+/// file and environment variables as the bot.
+#[derive(Parser)]
+#[command(name = "randomnamecord-cli", version)]
+struct Cli {
+    /// Output format: text, csv or json
+    #[arg(long, short, global = true, default_value = "text")]
+    format: ExportFormat,
+    #[command(subcommand)]
+    command: CliCommand,
+}
+
+// Parsed once, so the size of the name options doesn't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+enum CliCommand {
+    #[command(flatten)]
+    Records(RecordCommand),
+    /// Run /name and /about_name interactively, as a simulated user
+    Repl {
+        /// A database to read server settings and user preferences from,
+        /// e.g. a copy of the bot's; an empty one if left out
+        #[arg(long)]
+        database: Option<String>,
+    },
+}
+
+/// The subcommands that output name records.
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+enum RecordCommand {
+    /// Generate random names, as /name does
+    Name {
+        /// m, f or u
+        #[arg(long, short)]
+        gender: Option<Gender>,
+        /// coherent, chaotic, medieval, mythology, biblical or ancient
+        #[arg(long, short)]
+        mode: Option<GenMode>,
+        /// How many names to generate
+        #[arg(long, short, default_value_t = 1,
+              value_parser = clap::value_parser!(u32).range(1..=MAX_COUNT as i64))]
+        count: u32,
+        /// Usages for the given name, e.g. "iri:3 ita"
+        #[arg(long)]
+        given_usage: Option<UsageSpec>,
+        /// Usages for the surname, e.g. "jap kor"
+        #[arg(long)]
+        surname_usage: Option<UsageSpec>,
+        /// Usages for both names, e.g. "iri:3 ita:1"
+        #[arg(long)]
+        heritage: Option<UsageSpec>,
+        /// Usages or groups to exclude, e.g. "mythology bibl"
+        #[arg(long)]
+        exclude: Option<Exclusions>,
+        /// Constraints on the name, e.g. "starts:K length:-6 alliterative"
+        #[arg(long)]
+        constraints: Option<Constraints>,
+        /// Seed for repeatable choices; later names use the following seeds
+        #[arg(long)]
+        seed: Option<u64>,
+        /// btn for BehindTheName, or local for the bot's own name lists,
+        /// which need no network and always give the same names for a seed
+        #[arg(long, default_value = "btn")]
+        backend: Backend,
+        /// Always put the given name first
+        #[arg(long)]
+        western_order: bool,
+        /// Join double surnames with y/i/e
+        #[arg(long)]
+        surname_conjunction: bool,
+    },
+    /// Find a surname for a first name, as /debug_name does
+    SurnameFor {
+        first_name: String,
+        /// m, f or u
+        #[arg(long, short)]
+        gender: Option<Gender>,
+    },
+    /// Look a name up on BehindTheName, as /about_name does
+    About {
+        #[arg(required = true)]
+        name: Vec<String>,
+    },
+}
+
+/// Generate `count` names, each in a blocking task like the bot's.
+async fn names(options: NameOptions, count: u32) -> Result<Vec<Name>, Error> {
+    let mut names = vec![];
+    for index in 0..count {
+        let options = NameOptions {
+            seed: options.seed.wrapping_add(index.into()),
+            ..options.clone()
+        };
+        names.push(tokio::task::spawn_blocking(move || _name(options)).await??);
+    }
+    Ok(names)
+}
+
+async fn records(command: RecordCommand) -> Result<Vec<NameRecord>, Error> {
+    Ok(match command {
+        RecordCommand::Name {
+            gender,
+            mode,
+            count,
+            given_usage,
+            surname_usage,
+            heritage,
+            exclude,
+            constraints,
+            seed,
+            backend,
+            western_order,
+            surname_conjunction,
+        } => {
+            let mut options = NameOptions {
+                gender,
+                mode,
+                given_usage: given_usage.or_else(|| heritage.clone()),
+                surname_usage: surname_usage.or(heritage),
+                exclusions: exclude.unwrap_or_default(),
+                constraints: constraints.unwrap_or_default(),
+                seed: seed.unwrap_or_else(random_seed),
+                backend,
+            };
+            options.fill_defaults(&config().defaults);
+            let format_options = FormatOptions {
+                western_order,
+                surname_conjunction,
+            };
+            names(options, count)
+                .await?
+                .iter()
+                .map(|name| NameRecord::new(name, format_options, None))
+                .collect()
+        }
+        RecordCommand::SurnameFor { first_name, gender } => {
+            let name = tokio::task::spawn_blocking(move || _dbg_name(first_name, gender)).await??;
+            vec![NameRecord::new(&name, FormatOptions::default(), None)]
+        }
+        RecordCommand::About { name } => {
+            let names = name
+                .iter()
+                .flat_map(|name| name.split_whitespace())
+                .map(String::from)
+                .collect::<Vec<_>>();
+            let parts = _about(&names).await?;
+            if parts.is_empty() {
+                Err(format!("{} not found on BehindTheName", names.join(" ")))?;
+            }
+            vec![NameRecord {
+                role: None,
+                full_name: names.join(" "),
+                usage: None,
+                given_usage: None,
+                parts,
+                notes: None,
+            }]
+        }
+    })
+}
+
+/// Run the command line given to the program, returning its exit code.
+pub async fn run() -> ExitCode {
+    let cli = Cli::parse();
+
+    // The REPL names offline unless told otherwise, so it runs without a
+    // BehindTheName API key.
+    let needs_btn_api_key = !matches!(cli.command, CliCommand::Repl { .. });
+    let config = match Config::load(false, needs_btn_api_key) {
+        Ok(config) => config.install(),
+        Err(e) => {
+            eprintln!("Invalid configuration:\n{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    // Logs go to stderr, to keep stdout for the names.
+    tracing_subscriber::fmt()
+        .with_max_level(config.log_level)
+        .with_writer(std::io::stderr)
+        .init();
+
+    let command = match cli.command {
+        CliCommand::Records(command) => command,
+        CliCommand::Repl { database } => return run_repl(database.as_deref()).await,
+    };
+
+    match records(command).await {
+        Ok(records) => {
+            println!("{}", export(&records, cli.format).0);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run the REPL on a database, or on an empty one in memory.
+async fn run_repl(database: Option<&str>) -> ExitCode {
+    let storage = match Storage::open(database.unwrap_or(":memory:")) {
+        Ok(storage) => storage,
+        Err(e) => {
+            eprintln!("At opening the database: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    match repl::run(storage).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
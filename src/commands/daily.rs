@@ -0,0 +1,266 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Days, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use poise::serenity_prelude::{GuildChannel, Http};
+use tokio::sync::Semaphore;
+use tokio::time::MissedTickBehavior;
+use tracing::error;
+
+use crate::btn::random_seed;
+use crate::commands::names::{_name, name_message};
+use crate::config::config;
+use crate::naming::format::{full_name, name_parts, FormatOptions};
+use crate::resources::types::*;
+use crate::storage::daily::DailyName;
+use crate::storage::{unix_now, Storage};
+
+/// How often to check for names of the day that are due.
+const TICK: Duration = Duration::from_secs(60);
+const DEFAULT_NO_REPEAT_DAYS: u32 = 30;
+const MAX_NO_REPEAT_DAYS: u32 = 365;
+/// How many names of the day to generate at once. Their requests share one
+/// queue, but a guild's retries don't hold up the next guild's post.
+const MAX_CONCURRENT_POSTS: usize = 4;
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| format!("Invalid time {}; use 24-hour HH:MM, e.g. 09:30", time))
+}
+
+fn parse_time_zone(time_zone: &str) -> Result<Tz, String> {
+    time_zone.parse::<Tz>().map_err(|_| {
+        format!(
+            "Unknown time zone {}; use a name like Europe/Paris or America/New_York",
+            time_zone
+        )
+    })
+}
+
+/// A guild's time zone, today's date there, and the time to post at.
+fn local_schedule(daily: &DailyName) -> Option<(Tz, NaiveDate, NaiveTime)> {
+    let tz = parse_time_zone(&daily.time_zone).ok()?;
+    let time = parse_time(&daily.time).ok()?;
+    Some((tz, Utc::now().with_timezone(&tz).date_naive(), time))
+}
+
+/// Today's local date, if today's name is due and hasn't been posted.
+fn due_date(daily: &DailyName) -> Option<String> {
+    let tz = parse_time_zone(&daily.time_zone).ok()?;
+    let time = parse_time(&daily.time).ok()?;
+    let now = Utc::now().with_timezone(&tz);
+    let today = now.date_naive().to_string();
+
+    (now.time() >= time && daily.last_posted.as_deref() != Some(&today)).then_some(today)
+}
+
+/// When the next name is due, in Unix seconds.
+fn next_post(daily: &DailyName) -> Option<i64> {
+    let (tz, today, time) = local_schedule(daily)?;
+    let date = if daily.last_posted.as_deref() == Some(&today.to_string()) {
+        today.checked_add_days(Days::new(1))?
+    } else {
+        today
+    };
+    tz.from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|datetime| datetime.timestamp())
+}
+
+fn describe(daily: &DailyName) -> String {
+    let next = next_post(daily)
+        .map(|timestamp| format!(", next <t:{}:R>", timestamp))
+        .unwrap_or_default();
+    format!(
+        "Posting a name of the day in <#{}> at {} ({}){}. First names aren't repeated within {} days.",
+        daily.channel_id, daily.time, daily.time_zone, next, daily.no_repeat_days
+    )
+}
+
+async fn post_daily_name(http: &Http, storage: &Storage, daily: &DailyName) -> Result<(), Error> {
+    let since = unix_now() - i64::from(daily.no_repeat_days) * 24 * 60 * 60;
+    let settings = storage.guild_settings(daily.guild_id)?;
+    let defaults = settings.defaults.clone().or(config().defaults.clone());
+
+    let mut name_options = NameOptions {
+        exclusions: settings.exclusions.clone(),
+        seed: random_seed(),
+        ..Default::default()
+    };
+    name_options.fill_defaults(&defaults);
+    // Recent first names are re-rolled within the request budget, ignoring
+    // case and accents, as /no_repeat does.
+    let recent = storage.daily_first_names_since(daily.guild_id, since)?;
+    name_options
+        .constraints
+        .avoid_first_names(recent.iter().map(String::as_str));
+    let name = tokio::task::spawn_blocking(move || _name(name_options)).await??;
+
+    let options = FormatOptions::default();
+    let mut message = name_message(&name, options, None);
+    message.content = format!("Name of the day: {}", message.content);
+    if let (Some(usage), None, Some(embed)) = (&name.usage, &name.given_usage, &mut message.embed) {
+        embed
+            .fields
+            .push(("Usage".into(), usage.usage_full.clone(), true));
+    }
+
+    daily
+        .channel_id
+        .send_message(http, |m| {
+            m.content(message.content);
+            if let Some(embed) = message.embed {
+                m.embed(|e| e.title(embed.title).fields(embed.fields));
+            }
+            m
+        })
+        .await?;
+
+    storage.add_daily_post(
+        daily.guild_id,
+        &name.first_name,
+        &full_name(&name_parts(&name, options)),
+        unix_now(),
+    )?;
+
+    Ok(())
+}
+
+/// Check every minute for names of the day that are due, and post them.
+/// The schedule is read from storage each time, so it survives restarts,
+/// and a name that came due while the bot was down is posted on startup.
+pub(crate) fn start_daily_names(http: Arc<Http>, storage: Arc<Storage>) {
+    let posts = Arc::new(Semaphore::new(MAX_CONCURRENT_POSTS));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            let daily_names = match storage.daily_names() {
+                Ok(daily_names) => daily_names,
+                Err(e) => {
+                    error!("At loading names of the day: {}", e);
+                    continue;
+                }
+            };
+
+            for daily in daily_names {
+                let Some(today) = due_date(&daily) else {
+                    continue;
+                };
+                // Mark the day first, so a failing post isn't retried every
+                // minute.
+                if let Err(e) = storage.set_daily_posted(daily.guild_id, &today) {
+                    error!("At name of the day for {}: {}", daily.guild_id, e);
+                    continue;
+                }
+
+                let (http, storage, posts) = (http.clone(), storage.clone(), posts.clone());
+                tokio::spawn(async move {
+                    let _permit = posts.acquire_owned().await;
+                    if let Err(e) = post_daily_name(&http, &storage, &daily).await {
+                        error!("At name of the day for {}: {}", daily.guild_id, e);
+                    }
+                });
+            }
+        }
+    });
+}
+
+/// Post a random name every day.
+///
+/// Post a random name every day.
+///
+/// /name_of_the_day set picks the channel, the time (24-hour HH:MM) \
+/// and the time zone (e.g. Europe/Paris; UTC by default). A first \
+/// name is never posted again within no_repeat_days (30 by default).
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("daily_set", "daily_show", "daily_stop")
+)]
+pub(crate) async fn name_of_the_day(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Post a name of the day in a channel.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "set")]
+pub(crate) async fn daily_set(
+    ctx: Context<'_>,
+    #[description = "Channel to post in"] channel: GuildChannel,
+    #[description = "Time to post at, in 24-hour HH:MM"] time: String,
+    #[description = "Time zone, optional: e.g. Europe/Paris (default UTC)"] time_zone: Option<
+        String,
+    >,
+    #[description = "Days before a first name can repeat, optional (default 30)"]
+    no_repeat_days: Option<u32>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let storage = &ctx.data().storage;
+    let time_zone = time_zone.unwrap_or_else(|| "UTC".into());
+
+    let (tz, post_time) = match (parse_time_zone(&time_zone), parse_time(&time)) {
+        (Ok(tz), Ok(post_time)) => (tz, post_time),
+        (Err(e), _) | (_, Err(e)) => {
+            ctx.say(e).await?;
+            return Ok(());
+        }
+    };
+
+    // Start tomorrow if today's time has passed, or today's name was posted.
+    let now = Utc::now().with_timezone(&tz);
+    let today = now.date_naive().to_string();
+    let posted_today = storage
+        .daily_name(guild_id)?
+        .is_some_and(|daily| daily.last_posted.as_deref() == Some(&today));
+    let last_posted = (now.time() >= post_time || posted_today).then_some(today);
+
+    let daily = DailyName {
+        guild_id,
+        channel_id: channel.id,
+        time: post_time.format("%H:%M").to_string(),
+        time_zone: tz.name().into(),
+        no_repeat_days: no_repeat_days
+            .unwrap_or(DEFAULT_NO_REPEAT_DAYS)
+            .min(MAX_NO_REPEAT_DAYS),
+        last_posted,
+    };
+    storage.set_daily_name(&daily)?;
+
+    ctx.say(describe(&daily)).await?;
+    Ok(())
+}
+
+/// Show when and where the name of the day is posted.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "show")]
+pub(crate) async fn daily_show(ctx: Context<'_>) -> Result<(), Error> {
+    let message = match ctx.data().storage.daily_name(ctx.guild_id().unwrap())? {
+        Some(daily) => describe(&daily),
+        None => "No name of the day is posted in this server.".into(),
+    };
+
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Stop posting a name of the day.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "stop")]
+pub(crate) async fn daily_stop(ctx: Context<'_>) -> Result<(), Error> {
+    let message = if ctx
+        .data()
+        .storage
+        .remove_daily_name(ctx.guild_id().unwrap())?
+    {
+        "Stopped posting a name of the day."
+    } else {
+        "No name of the day is posted in this server."
+    };
+
+    ctx.say(message).await?;
+    Ok(())
+}
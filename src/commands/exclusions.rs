@@ -0,0 +1,99 @@
+use crate::commands::names::guild_exclusions;
+use crate::naming::filter::Exclusions;
+use crate::resources::types::*;
+
+fn describe(exclusions: &Exclusions) -> String {
+    if exclusions.is_empty() {
+        "No usages are excluded in this server.".into()
+    } else {
+        format!("Excluded in this server: {}", exclusions)
+    }
+}
+
+/// Manage usages excluded from names generated in this server.
+///
+/// Manage usages excluded from names generated in this server.
+///
+/// Exclusions are BehindTheName usage codes (e.g. bibl, gre-myth) \
+/// or groups: medieval, mythology, biblical, ancient. They apply \
+/// to every /name and /family in this server, on top of any \
+/// exclude option passed to the command.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands(
+        "exclusions_add",
+        "exclusions_remove",
+        "exclusions_list",
+        "exclusions_clear"
+    )
+)]
+pub(crate) async fn exclusions(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Exclude usages or groups from names generated in this server.
+#[poise::command(slash_command, prefix_command, guild_only, ephemeral, rename = "add")]
+pub(crate) async fn exclusions_add(
+    ctx: Context<'_>,
+    #[description = "Usages or groups, e.g. mythology bibl"] usages: Exclusions,
+) -> Result<(), Error> {
+    let settings = ctx
+        .data()
+        .storage
+        .update_guild_settings(ctx.guild_id().unwrap(), |settings| {
+            settings.exclusions = settings.exclusions.union(&usages)
+        })?;
+    let message = describe(&settings.exclusions);
+
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Stop excluding usages or groups in this server.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    ephemeral,
+    rename = "remove"
+)]
+pub(crate) async fn exclusions_remove(
+    ctx: Context<'_>,
+    #[description = "Usages or groups, e.g. mythology bibl"] usages: Exclusions,
+) -> Result<(), Error> {
+    let settings = ctx
+        .data()
+        .storage
+        .update_guild_settings(ctx.guild_id().unwrap(), |settings| {
+            settings.exclusions = settings.exclusions.difference(&usages)
+        })?;
+    let message = describe(&settings.exclusions);
+
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// List the usages excluded in this server.
+#[poise::command(slash_command, prefix_command, guild_only, ephemeral, rename = "list")]
+pub(crate) async fn exclusions_list(ctx: Context<'_>) -> Result<(), Error> {
+    let message = describe(&guild_exclusions(ctx));
+
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Remove all exclusions in this server.
+#[poise::command(slash_command, prefix_command, guild_only, ephemeral, rename = "clear")]
+pub(crate) async fn exclusions_clear(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.data()
+        .storage
+        .update_guild_settings(ctx.guild_id().unwrap(), |settings| {
+            settings.exclusions = Exclusions::default()
+        })?;
+
+    ctx.say(describe(&Exclusions::default())).await?;
+    Ok(())
+}
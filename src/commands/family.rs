@@ -0,0 +1,331 @@
+use behindthename::types::*;
+use rand::Rng;
+
+use crate::btn::{random_seed, BtnSession};
+use crate::commands::names::{
+    _coherent_name, _family_name, _given_name, _patronymic, an_error_occurred, edit_message,
+    guild_exclusions, reroll, send_export, MessageContent, MessageEmbed,
+};
+use crate::commands::prefs::format_options;
+use crate::config::config;
+use crate::naming::constraints::Constraints;
+use crate::naming::export::NameRecord;
+use crate::naming::filter::Exclusions;
+use crate::naming::format::{name_parts, FormatOptions};
+use crate::naming::{patronymic, surname};
+use crate::resources::types::*;
+use crate::resources::usages::{double_surname_style, patronymic_style};
+
+const MAX_FAMILY_SIZE: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Parent,
+    Child,
+}
+
+struct Member {
+    role: Role,
+    gender: Gender,
+    name: Name,
+}
+
+impl Member {
+    fn title(&self) -> &'static str {
+        match (self.role, self.gender) {
+            (Role::Parent, Gender::Male) => "Father",
+            (Role::Parent, Gender::Female) => "Mother",
+            (Role::Parent, _) => "Parent",
+            (Role::Child, Gender::Male) => "Son",
+            (Role::Child, Gender::Female) => "Daughter",
+            (Role::Child, _) => "Child",
+        }
+    }
+}
+
+/// A parent other than the first takes their own patronymic and, in cultures
+/// with double surnames, their own surnames; otherwise they share the head's.
+fn _spouse(
+    session: &BtnSession,
+    gender: Gender,
+    first_name: String,
+    head: &Name,
+    usage: &Usage,
+) -> Result<Name, String> {
+    let code = usage.usage_code.as_str();
+
+    let patronymic = match patronymic_style(code) {
+        Some(style) => Some(_patronymic(session, style, gender, code)?),
+        None => None,
+    };
+
+    let (last_name_result, second_last_name) = match double_surname_style(code) {
+        Some(_) => {
+            let family_name = _family_name(session, gender, usage)?;
+            (
+                family_name
+                    .last_name
+                    .ok_or_else(|| "At spouse surname: none found".into()),
+                family_name.second_last_name,
+            )
+        }
+        None => (
+            surname_for(code, &head.last_name_result, gender),
+            head.second_last_name.clone(),
+        ),
+    };
+
+    Ok(Name {
+        first_name,
+        patronymic,
+        last_name_result,
+        second_last_name,
+        epithet: None,
+        usage: Some(usage.clone()),
+        given_usage: None,
+    })
+}
+
+/// A child takes their patronymic from their father and, in cultures with
+/// double surnames, the first surname of each parent.
+fn _child(
+    session: &BtnSession,
+    gender: Gender,
+    first_name: String,
+    members: &[Member],
+    usage: &Usage,
+) -> Result<Name, String> {
+    let code = usage.usage_code.as_str();
+    let head = &members[0].name;
+    let parents = members
+        .iter()
+        .filter(|member| member.role == Role::Parent)
+        .collect::<Vec<_>>();
+    let father = parents
+        .iter()
+        .find(|member| member.gender == Gender::Male)
+        .or(parents.first());
+    let mother = father.and_then(|father| {
+        parents
+            .iter()
+            .find(|member| !std::ptr::eq(**member, *father))
+    });
+
+    let patronymic = match (patronymic_style(code), father) {
+        (Some(style), Some(father))
+            if father.gender != Gender::Female || style.allows_matronymic() =>
+        {
+            Some(patronymic::derive(
+                style,
+                father.name.first_name.clone(),
+                gender,
+                father.gender == Gender::Female,
+                &mut *session.rng(),
+            ))
+        }
+        (Some(style), _) => Some(_patronymic(session, style, gender, code)?),
+        (None, _) => None,
+    };
+
+    let (last_name_result, second_last_name) = match (double_surname_style(code), father) {
+        (Some(_), Some(father)) => (
+            father.name.last_name_result.clone(),
+            mother
+                .and_then(|mother| mother.name.last_name_result.clone().ok())
+                .or_else(|| father.name.second_last_name.clone()),
+        ),
+        _ => (
+            surname_for(code, &head.last_name_result, gender),
+            head.second_last_name.clone(),
+        ),
+    };
+
+    Ok(Name {
+        first_name,
+        patronymic,
+        last_name_result,
+        second_last_name,
+        epithet: None,
+        usage: Some(usage.clone()),
+        given_usage: None,
+    })
+}
+
+/// The head's surname in the form a member of the given gender carries,
+/// e.g. Ivanova for the wife of an Ivanov.
+fn surname_for(
+    code: &str,
+    last_name: &Result<String, String>,
+    gender: Gender,
+) -> Result<String, String> {
+    last_name
+        .as_ref()
+        .map(|last_name| surname::gendered_for(code, last_name, gender))
+        .map_err(Clone::clone)
+}
+
+/// Gender::Any as male or female, at random; other genders as they are.
+fn settle_gender(session: &BtnSession, gender: Gender) -> Gender {
+    match gender {
+        Gender::Any if session.rng().gen_bool(0.5) => Gender::Male,
+        Gender::Any => Gender::Female,
+        gender => gender,
+    }
+}
+
+fn _family(
+    parent_genders: Vec<Gender>,
+    child_genders: Vec<Gender>,
+    exclusions: Exclusions,
+) -> Result<Vec<Member>, String> {
+    let config = config();
+    let session = BtnSession::new(&config.btn_api_key, config.request_interval, random_seed());
+
+    // Members of any gender are settled on one before their name is drawn,
+    // so the given name, patronymic and title all agree.
+    let mut roles = parent_genders
+        .into_iter()
+        .map(|gender| (Role::Parent, gender))
+        .chain(
+            child_genders
+                .into_iter()
+                .map(|gender| (Role::Child, gender)),
+        )
+        .map(|(role, gender)| (role, settle_gender(&session, gender)));
+
+    let (head_role, head_gender) = roles.next().ok_or("A family needs at least one member")?;
+
+    let head = reroll(&session, || {
+        _coherent_name(&session, head_gender, &exclusions, &Constraints::default())
+    })?;
+    let usage = match &head.usage {
+        Some(usage) => usage.clone(),
+        None => {
+            return Err(format!(
+                "No surname found for {}: {}",
+                head.first_name,
+                head.last_name_result.unwrap_err()
+            ))
+        }
+    };
+
+    let mut members = vec![Member {
+        role: head_role,
+        gender: head_gender,
+        name: head,
+    }];
+
+    for (role, gender) in roles {
+        let first_name = _given_name(&session, gender, &usage.usage_code)?;
+        let name = match role {
+            Role::Parent => _spouse(&session, gender, first_name, &members[0].name, &usage)?,
+            Role::Child => _child(&session, gender, first_name, &members, &usage)?,
+        };
+        members.push(Member { role, gender, name });
+    }
+
+    Ok(members)
+}
+
+fn family_message(members: &[Member], options: FormatOptions) -> MessageContent {
+    let head = &members[0].name;
+    let usage = head
+        .usage
+        .as_ref()
+        .map(|usage| usage.usage_full.as_str())
+        .unwrap_or_default();
+
+    let content = match &head.last_name_result {
+        Ok(last_name) => format!("The {} family ({})", last_name, usage),
+        Err(_) => format!("Family ({})", usage),
+    };
+
+    let fields = members
+        .iter()
+        .map(|member| {
+            let links = name_parts(&member.name, options)
+                .iter()
+                .map(|part| part.link())
+                .collect::<Vec<_>>()
+                .join(" ");
+            (member.title().into(), links, false)
+        })
+        .collect();
+
+    MessageContent {
+        content,
+        embed: Some(MessageEmbed {
+            title: "BehindTheName".into(),
+            fields,
+        }),
+    }
+}
+
+/// Generate a family sharing a surname and culture.
+///
+/// Generate a family sharing a surname and culture.
+///
+/// parents and children take a list of genders, one letter \
+/// per member: m, f, u, or x for any gender. For example, \
+/// parents: mf children: fxm generates two parents and three \
+/// children. By default, two parents (mf) and two children (xx).
+///
+/// Pass export: text|csv|json to also get the family as a file.
+///
+/// Pass exclude to keep usages or groups out of the family, \
+/// on top of the server's exclusions.
+#[allow(clippy::too_many_arguments)]
+#[poise::command(prefix_command, slash_command, broadcast_typing)]
+pub(crate) async fn family(
+    ctx: Context<'_>,
+    #[description = "Genders of the parents, optional: e.g. mf"] parents: Option<GenderList>,
+    #[description = "Genders of the children, optional: e.g. fxm"] children: Option<GenderList>,
+    #[description = "Also export the family, optional: text|csv|json"] export: Option<ExportFormat>,
+    #[description = "Always put the given name first, optional"] western_order: Option<bool>,
+    #[description = "Join double surnames with y/i/e, optional"] surname_conjunction: Option<bool>,
+    #[description = "Usages or groups to exclude, optional: e.g. mythology bibl"] exclude: Option<
+        Exclusions,
+    >,
+) -> Result<(), Error> {
+    let parent_genders = parents.map_or(vec![Gender::Male, Gender::Female], |p| p.0);
+    let child_genders = children.map_or(vec![Gender::Any, Gender::Any], |c| c.0);
+
+    if parent_genders.len() + child_genders.len() > MAX_FAMILY_SIZE {
+        ctx.say(format!(
+            "Families are limited to {} members.",
+            MAX_FAMILY_SIZE
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let working_msg = ctx.say("Working...").await?;
+
+    let exclusions = guild_exclusions(ctx).union(&exclude.unwrap_or_default());
+
+    let family =
+        tokio::task::spawn_blocking(move || _family(parent_genders, child_genders, exclusions))
+            .await?;
+
+    let members = match family {
+        Ok(members) => members,
+        Err(e) => {
+            ctx.say(an_error_occurred(e.clone())).await?;
+            Err(e)?
+        }
+    };
+
+    let options = format_options(ctx, western_order, surname_conjunction);
+
+    edit_message(ctx, working_msg, family_message(&members, options)).await?;
+
+    if let Some(format) = export {
+        let records = members
+            .iter()
+            .map(|member| NameRecord::new(&member.name, options, Some(member.title().into())))
+            .collect::<Vec<_>>();
+        send_export(ctx, &records, format, "family").await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use poise::serenity_prelude::UserId;
+
+use crate::commands::names::send_export;
+use crate::naming::export::NameRecord;
+use crate::resources::types::*;
+use crate::storage::favorites::Favorite;
+use crate::storage::{unix_now, Storage};
+
+const MAX_FAVORITES: usize = 200;
+const PAGE_SIZE: usize = 10;
+
+pub(crate) fn save_button_id(ctx: Context<'_>) -> String {
+    format!("{}save", ctx.id())
+}
+
+/// Save a name to a user's favorites, returning the reply to show them.
+pub(crate) fn save_favorite(storage: &Storage, user_id: UserId, record: &NameRecord) -> String {
+    match storage.favorite_count(user_id) {
+        Ok(count) if count >= MAX_FAVORITES => {
+            return format!(
+                "You already have {} favorites; remove some with /favorites remove.",
+                MAX_FAVORITES
+            )
+        }
+        Ok(_) => {}
+        Err(e) => return format!("Couldn't save {}: {}", record.full_name, e),
+    }
+
+    match storage.add_favorite(user_id, record, unix_now()) {
+        Ok(true) => format!("Saved {} to your favorites.", record.full_name),
+        Ok(false) => format!("{} is already in your favorites.", record.full_name),
+        Err(e) => format!("Couldn't save {}: {}", record.full_name, e),
+    }
+}
+
+/// A favorite as listed, numbered from 1 among the user's favorites.
+fn describe(number: usize, favorite: &Favorite) -> String {
+    let record = &favorite.record;
+    let usages = record
+        .usages()
+        .map(|usages| format!(" ({})", usages))
+        .unwrap_or_default();
+    format!(
+        "`{}` {}{}, saved <t:{}:d>",
+        number,
+        record.links(),
+        usages,
+        favorite.created_at
+    )
+}
+
+/// Keep the names you like.
+///
+/// Keep the names you like.
+///
+/// Press ⭐ Save under a generated name to add it to your \
+/// favorites, along with its BehindTheName links and usages.
+///
+/// /favorites list shows them with their numbers, /favorites \
+/// remove forgets one by number, and /favorites export sends \
+/// them as a text, CSV or JSON file.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommands("favorites_list", "favorites_remove", "favorites_export")
+)]
+pub(crate) async fn favorites(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// List your favorite names.
+#[poise::command(slash_command, prefix_command, ephemeral, rename = "list")]
+pub(crate) async fn favorites_list(ctx: Context<'_>) -> Result<(), Error> {
+    let favorites = ctx.data().storage.favorites(ctx.author().id)?;
+    if favorites.is_empty() {
+        ctx.say("You have no favorites yet. Press ⭐ Save under a generated name to add one.")
+            .await?;
+        return Ok(());
+    }
+
+    let lines = favorites
+        .iter()
+        .enumerate()
+        .map(|(index, favorite)| describe(index + 1, favorite))
+        .collect::<Vec<_>>();
+    let pages = lines
+        .chunks(PAGE_SIZE)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let lines = chunk.join("\n");
+            format!(
+                "{}\n\nPage {} of {}",
+                lines,
+                index + 1,
+                favorites.len().div_ceil(PAGE_SIZE)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    poise::builtins::paginate(ctx, &pages.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+    Ok(())
+}
+
+/// Remove a name from your favorites.
+#[poise::command(slash_command, prefix_command, ephemeral, rename = "remove")]
+pub(crate) async fn favorites_remove(
+    ctx: Context<'_>,
+    #[description = "Number of the favorite, as shown by /favorites list"] number: u32,
+) -> Result<(), Error> {
+    let storage = &ctx.data().storage;
+    let user_id = ctx.author().id;
+    let favorites = storage.favorites(user_id)?;
+    let favorite = (number as usize)
+        .checked_sub(1)
+        .and_then(|index| favorites.get(index));
+
+    let message = match favorite {
+        Some(favorite) if storage.remove_favorite(user_id, favorite.id)? => {
+            format!("Removed {} from your favorites.", favorite.record.full_name)
+        }
+        _ => format!("You have no favorite numbered {}.", number),
+    };
+
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Export your favorite names as a file.
+#[poise::command(slash_command, prefix_command, ephemeral, rename = "export")]
+pub(crate) async fn favorites_export(
+    ctx: Context<'_>,
+    #[description = "File format, optional: text|csv|json (default text)"] format: Option<
+        ExportFormat,
+    >,
+) -> Result<(), Error> {
+    let favorites = ctx.data().storage.favorites(ctx.author().id)?;
+    if favorites.is_empty() {
+        ctx.say("You have no favorites yet.").await?;
+        return Ok(());
+    }
+
+    let records = favorites
+        .into_iter()
+        .map(|favorite| favorite.record)
+        .collect::<Vec<_>>();
+    send_export(
+        ctx,
+        &records,
+        format.unwrap_or(ExportFormat::Text),
+        "favorites",
+    )
+    .await
+}
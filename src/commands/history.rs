@@ -0,0 +1,136 @@
+use behindthename::types::Gender;
+use poise::serenity_prelude::{GuildId, User, UserId};
+use tracing::error;
+
+use crate::naming::format::{full_name, name_parts, FormatOptions};
+use crate::resources::types::*;
+use crate::resources::usages::usage_for;
+use crate::storage::history::{HistoryEntry, HistoryFilter};
+use crate::storage::{unix_now, Storage};
+
+const PAGE_SIZE: usize = 10;
+/// The most entries to page through at once.
+const MAX_ENTRIES: usize = 100;
+
+/// Remember a name generated for a user, in a guild or in DMs. Failing to
+/// remember it isn't worth failing the command over, so errors are only
+/// logged.
+pub(crate) fn add_to_history(
+    storage: &Storage,
+    guild_id: Option<GuildId>,
+    user_id: UserId,
+    name: &Name,
+    mode: String,
+    gender: Gender,
+) {
+    let entry = HistoryEntry {
+        guild_id,
+        user_id,
+        first_name: name.first_name.clone(),
+        last_name: name.last_name_result.clone().ok(),
+        full_name: full_name(&name_parts(name, FormatOptions::default())),
+        usage: name.usage.as_ref().map(|usage| usage.usage_code.clone()),
+        given_usage: name
+            .given_usage
+            .as_ref()
+            .map(|usage| usage.usage_code.clone()),
+        mode,
+        gender: gender.to_string(),
+        created_at: unix_now(),
+    };
+
+    if let Err(e) = storage.add_history(&entry) {
+        error!("At recording name history: {}", e);
+    }
+}
+
+fn usage_name(code: &str) -> String {
+    usage_for(code, Gender::Any).usage_full
+}
+
+fn describe(entry: &HistoryEntry) -> String {
+    let usages = match (&entry.given_usage, &entry.usage) {
+        (Some(given_usage), Some(usage)) => {
+            format!("{} / {}", usage_name(given_usage), usage_name(usage))
+        }
+        (None, Some(usage)) => usage_name(usage),
+        _ => "unknown usage".into(),
+    };
+    format!(
+        "<t:{}:d> **{}** ({}, {}) by <@{}>",
+        entry.created_at, entry.full_name, usages, entry.mode, entry.user_id
+    )
+}
+
+/// List names generated recently.
+///
+/// List names generated recently.
+///
+/// By default, lists the names you generated with /name in this \
+/// server. Pass server: true for everyone's names in this server, \
+/// or user to list someone else's (in a server only, and only \
+/// their names from that server). Filter by mode (including \
+/// mixed), usage code (e.g. iri), gender (m, f, u) or part of \
+/// the name.
+#[allow(clippy::too_many_arguments)]
+#[poise::command(slash_command, prefix_command, ephemeral)]
+pub(crate) async fn name_history(
+    ctx: Context<'_>,
+    #[description = "List everyone's names in this server, optional"] server: Option<bool>,
+    #[description = "List this user's names, optional"] user: Option<User>,
+    #[description = "Only names of this mode, optional: e.g. coherent or mixed"] mode: Option<
+        String,
+    >,
+    #[description = "Only names of this usage, optional: e.g. iri"] usage: Option<String>,
+    #[description = "Only names of this gender, optional: m|f|u"] gender: Option<Gender>,
+    #[description = "Only names containing this, optional"] name: Option<String>,
+) -> Result<(), Error> {
+    // Outside a server there's no server to limit the history to, so
+    // someone else's would include servers the caller isn't in.
+    if let (None, Some(user)) = (ctx.guild_id(), &user) {
+        if user.id != ctx.author().id {
+            ctx.say("Others' names can only be listed in a server.")
+                .await?;
+            return Ok(());
+        }
+    }
+
+    let user_id = match (server.unwrap_or(false), user) {
+        (true, _) if ctx.guild_id().is_some() => None,
+        (_, Some(user)) => Some(user.id),
+        _ => Some(ctx.author().id),
+    };
+
+    let filter = HistoryFilter {
+        guild_id: ctx.guild_id(),
+        user_id,
+        mode: mode.map(|mode| mode.to_lowercase()),
+        usage: usage.map(|usage| usage.to_lowercase()),
+        gender: gender.map(|gender| gender.to_string()),
+        name,
+        ..Default::default()
+    };
+
+    let entries = ctx.data().storage.history(&filter, MAX_ENTRIES)?;
+    if entries.is_empty() {
+        ctx.say("No names found.").await?;
+        return Ok(());
+    }
+
+    let pages = entries
+        .chunks(PAGE_SIZE)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let lines = chunk.iter().map(describe).collect::<Vec<_>>().join("\n");
+            format!(
+                "{}\n\nPage {} of {}",
+                lines,
+                index + 1,
+                entries.len().div_ceil(PAGE_SIZE)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    poise::builtins::paginate(ctx, &pages.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+    Ok(())
+}
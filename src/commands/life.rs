@@ -0,0 +1,50 @@
+use behindthename::types::Gender;
+use poise::serenity_prelude::User;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::commands::names::{edit_message, name_message};
+use crate::naming::format::FormatOptions;
+use crate::naming::local::local_name;
+use crate::resources::names::{local_names, local_usage_codes};
+use crate::resources::types::*;
+
+/// Find out your name in another life.
+///
+/// Find out your name in another life.
+///
+/// Your name in another life is derived from your Discord user ID, \
+/// so it's always the same. It's picked from the bot's own list of \
+/// names rather than from BehindTheName.
+///
+/// Pass user to find out someone else's name, or gender or usage \
+/// (e.g. jap, iri) to find out who you'd have been elsewhere.
+#[poise::command(prefix_command, slash_command)]
+pub(crate) async fn another_life(
+    ctx: Context<'_>,
+    #[description = "Whose name to find, optional"] user: Option<User>,
+    #[description = "Gender of name, optional: m|f"] gender: Option<Gender>,
+    #[description = "Usage of name, optional: e.g. jap"] usage: Option<String>,
+) -> Result<(), Error> {
+    let usage = usage.map(|usage| usage.to_lowercase());
+    if let Some(code) = usage.as_deref().filter(|code| local_names(code).is_none()) {
+        ctx.say(format!(
+            "No names for usage {}. Usages: {}",
+            code,
+            local_usage_codes().join(", ")
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let working_msg = ctx.say("Working...").await?;
+
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let mut rng = ChaCha8Rng::seed_from_u64(user.id.0);
+    let name = local_name(&mut rng, gender.unwrap_or(Gender::Any), usage.as_deref())?;
+
+    let mut message = name_message(&name, FormatOptions::default(), None);
+    message.content = format!("In another life, {} is {}", user.name, message.content);
+
+    edit_message(ctx, working_msg, message).await
+}
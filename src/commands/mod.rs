@@ -1 +1,13 @@
+pub mod daily;
+pub mod exclusions;
+pub mod family;
+pub mod favorites;
+pub mod history;
+pub mod life;
 pub mod names;
+pub mod nickname;
+pub mod no_repeat;
+pub mod prefs;
+pub mod roster;
+pub mod roulette;
+pub mod settings;
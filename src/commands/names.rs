@@ -1,64 +1,181 @@
-use std::env;
-use std::thread::sleep;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use std::time::Duration;
 
-use behindthename::{lookup, random, session::Session, types::RateLimited::*, types::*};
+use behindthename::{lookup, random, types::RateLimited::*, types::*};
 use itertools::Itertools;
+use lazy_static::lazy_static;
+use poise::serenity_prelude::{
+    AttachmentType, ButtonStyle, CollectComponentInteraction, GuildId, InteractionResponseType,
+    MessageFlags, UserId,
+};
+use poise::ReplyHandle;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use reqwest::Client;
-use unicode_normalization::UnicodeNormalization;
-
-use poise::serenity_prelude::CacheHttp;
-
+use rand::Rng;
+use reqwest::{Client, StatusCode};
+use tracing::{error, warn};
+
+use crate::btn::{random_seed, BtnSession};
+use crate::commands::favorites::{save_button_id, save_favorite};
+use crate::commands::history::add_to_history;
+use crate::commands::nickname::{apply_nickname, check_renamable};
+use crate::commands::no_repeat::avoid_guild_repeats;
+use crate::commands::prefs::{format_options, layered_defaults, preferred_format, stored_prefs};
+use crate::commands::roster::{add_from_button, find_roster, roster_button_id};
+use crate::commands::settings::guild_settings;
+use crate::config::config;
+use crate::naming::constraints::Constraints;
+use crate::naming::era::epithet;
+use crate::naming::export::{export, NameRecord, PartRecord};
+use crate::naming::filter::Exclusions;
+use crate::naming::format::{full_name, name_parts, FormatOptions, PartKind};
+use crate::naming::links::*;
+use crate::naming::local;
+use crate::naming::patronymic::{self, Patronymic};
+use crate::naming::surname;
 use crate::resources::maps::*;
 use crate::resources::types::*;
+use crate::resources::usages::{
+    double_surname_style, era_usages, patronymic_style, usage_for, Era, PatronymicStyle,
+};
+use crate::storage::prefs::UserPrefs;
+use crate::storage::settings::GuildSettings;
+use crate::storage::{unix_now, Storage};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// How long the buttons under a generated name stay usable.
+const BUTTON_TIMEOUT: Duration = Duration::from_secs(15 * 60);
 
-struct Name {
-    first_name: String,
-    last_name_result: Result<String, String>,
+lazy_static! {
+    /// Whether BehindTheName pages exist, shared by /about_name and the HTTP
+    /// API.
+    static ref PAGE_CACHE: Mutex<HashMap<String, bool>> = Mutex::new(HashMap::new());
 }
 
-fn hyperlink(title: &str, url: &str) -> String {
-    format!("[{}]({})", title, url)
+pub(crate) fn _first_name(session: &BtnSession, gender: Gender) -> Result<String, String> {
+    let first_name_request = random::random_with_gender(gender);
+    match session.request(first_name_request) {
+        Allowed(JsonResponse::NameList(JsonNameList { names })) => {
+            Ok(names.first().unwrap().to_owned())
+        }
+        Allowed(_) => Err("At first name request: parsing issue".into()),
+        Failed(e) => Err(format!("At first name request: {:?}", e)),
+        Governed(_, _) => Err("At first name request: governed".into()),
+        ReqwestError(e) => Err(format!("At first name request: {}", e)),
+    }
 }
 
-fn lower_normalize(name: &str) -> String {
-    let nfc_name = name.to_lowercase().nfc().to_string();
-
-    NORM_AC.replace_all(&nfc_name, &NORM_CODES)
+pub(crate) fn _given_name(
+    session: &BtnSession,
+    gender: Gender,
+    usage_code: &str,
+) -> Result<String, String> {
+    let given_name_request = random::random_with_params(gender, Some(usage_code), Some(1), false);
+    match session.request(given_name_request) {
+        Allowed(JsonResponse::NameList(JsonNameList { names })) => names
+            .first()
+            .cloned()
+            .ok_or_else(|| "At given name request: none found".into()),
+        Allowed(_) => Err("At given name request: parsing issue".into()),
+        Failed(e) => Err(format!(
+            "At given name request for usage {}: {:?}",
+            usage_code, e
+        )),
+        Governed(_, _) => Err("At given name request: governed".into()),
+        ReqwestError(e) => Err(format!("At given name request: {}", e)),
+    }
 }
 
-fn first_name_url(first_name: &str) -> String {
-    format!(
-        "https://www.behindthename.com/name/{}",
-        lower_normalize(first_name)
-    )
-}
+pub(crate) fn _patronymic(
+    session: &BtnSession,
+    style: PatronymicStyle,
+    gender: Gender,
+    usage_code: &str,
+) -> Result<Patronymic, String> {
+    let matronymic = style.allows_matronymic() && session.rng().gen_ratio(1, 10);
+    let parent_gender = if matronymic {
+        Gender::Female
+    } else {
+        Gender::Male
+    };
 
-fn last_name_url(last_name: &str) -> String {
-    format!(
-        "https://surnames.behindthename.com/name/{}",
-        lower_normalize(last_name)
-    )
+    let parent_name = _given_name(session, parent_gender, usage_code)
+        .map_err(|e| format!("At parent name: {}", e))?;
+
+    Ok(patronymic::derive(
+        style,
+        parent_name,
+        gender,
+        matronymic,
+        &mut *session.rng(),
+    ))
 }
 
-fn first_name_hyperlink(first_name: &str) -> String {
-    hyperlink(first_name, &first_name_url(first_name))
+fn _last_name(session: &BtnSession, gender: Gender, usage: &Usage) -> Result<String, String> {
+    let last_name_request =
+        random::random_with_params(gender, Some(&*usage.usage_code), Some(1), true);
+    match session.request(last_name_request) {
+        Allowed(JsonResponse::NameList(JsonNameList { names })) => {
+            Ok(names.last().unwrap().to_owned())
+        }
+        Allowed(_) => Err("At last name request: parsing error".into()),
+        Failed(e) => Err(format!(
+            "At last name request for usage {:?}: {:?}",
+            usage, e
+        )),
+        Governed(_, _) => Err("At last name request: governed".into()),
+        ReqwestError(e) => Err(format!("At last name request: {}", e)),
+    }
 }
 
-fn last_name_hyperlink(last_name: &str) -> String {
-    hyperlink(last_name, &last_name_url(last_name))
+pub(crate) struct FamilyName {
+    pub(crate) last_name: Option<String>,
+    pub(crate) second_last_name: Option<String>,
+    pub(crate) patronymic: Option<Patronymic>,
 }
 
-fn _surname(session: Session, gender: Gender, first_name: String) -> Result<Name, String> {
-    sleep(Duration::from_millis(550));
+pub(crate) fn _family_name(
+    session: &BtnSession,
+    gender: Gender,
+    usage: &Usage,
+) -> Result<FamilyName, String> {
+    let code = usage.usage_code.as_str();
+    let style = patronymic_style(code);
+
+    if let Some(style) = style.filter(|style| style.replaces_surname()) {
+        return _patronymic(session, style, gender, code).map(|patronymic| FamilyName {
+            last_name: None,
+            second_last_name: None,
+            patronymic: Some(patronymic),
+        });
+    }
 
-    let usage_request = lookup::lookup(first_name.as_str());
+    let last_name = surname::gendered_for(code, &_last_name(session, gender, usage)?, gender);
+
+    let second_last_name = double_surname_style(code).and_then(|_| {
+        _last_name(session, gender, usage)
+            .map_err(|e| warn!("At second last name: {}", e))
+            .ok()
+    });
 
-    let possible_usages = match session.request(usage_request) {
+    let patronymic = style.and_then(|style| {
+        _patronymic(session, style, gender, code)
+            .map_err(|e| warn!("At patronymic: {}", e))
+            .ok()
+    });
+
+    Ok(FamilyName {
+        last_name: Some(last_name),
+        second_last_name,
+        patronymic,
+    })
+}
+
+pub(crate) fn _usages(session: &BtnSession, first_name: &str) -> Result<Vec<Usage>, String> {
+    let usage_request = lookup::lookup(first_name);
+
+    match session.request(usage_request) {
         Allowed(JsonResponse::NameDetails(JsonNameDetails(details))) => Ok(details
             .into_iter()
             .flat_map(|item| item.usages)
@@ -68,73 +185,48 @@ fn _surname(session: Session, gender: Gender, first_name: String) -> Result<Name
         Failed(e) => Err(format!("At usage request: {:?}", e)),
         Governed(_, _) => Err("At usage request: governed".into()),
         ReqwestError(e) => Err(format!("At usage request: {}", e)),
-    };
+    }
+}
 
-    let possible_usages_shuffled = possible_usages.map(|mut usages| {
-        usages.shuffle(&mut thread_rng());
-        usages
-    });
+pub(crate) fn _surname(
+    session: &BtnSession,
+    gender: Gender,
+    first_name: String,
+    exclusions: &Exclusions,
+) -> Result<Name, String> {
+    let possible_usages = _usages(session, &first_name);
 
-    let mut usage_augment = possible_usages_shuffled
-        .as_ref()
-        .map(|usages| {
-            usages
-                .iter()
-                .filter(|usage| USAGE_REGEX.is_match(&usage.usage_code))
-                .map(|usage| {
-                    let mut new_usage_code = None;
-                    for (pat, repl) in USAGE_MAP.iter() {
-                        if pat.is_match(&usage.usage_code) {
-                            new_usage_code =
-                                Some(pat.replace(&usage.usage_code, repl.to_string()).to_string());
-                            break;
-                        }
-                    }
-                    Usage {
-                        usage_code: new_usage_code.unwrap(),
-                        ..usage.clone()
-                    }
-                })
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_default();
+    _surname_for_usages(session, gender, first_name, possible_usages, exclusions)
+}
 
-    let possible_usages_augmented = possible_usages_shuffled.map(|mut usages| {
-        usages.append(&mut usage_augment);
+fn _surname_for_usages(
+    session: &BtnSession,
+    gender: Gender,
+    first_name: String,
+    possible_usages: Result<Vec<Usage>, String>,
+    exclusions: &Exclusions,
+) -> Result<Name, String> {
+    let possible_usages_augmented = possible_usages.map(|mut usages| {
+        usages.retain(|usage| !exclusions.excludes(&usage.usage_code));
+        usages.shuffle(&mut *session.rng());
+        let mut usages = usage_rules().augment(usages);
+        usages.retain(|usage| !exclusions.excludes(&usage.usage_code));
         usages
     });
 
     let last_name_result = possible_usages_augmented.and_then(|usages| {
         let mut errs_acc = vec![];
-        let mut result = Err(format!("{} Usages", usages.len()));
+        let mut result: Result<(FamilyName, Usage), String> =
+            Err(format!("{} Usages", usages.len()));
         for usage in usages {
-            sleep(Duration::from_millis(550));
-
             let last_name_gender = match gender {
                 Gender::Any => usage.usage_gender,
                 _ => gender,
             };
-            let last_name_request = random::random_with_params(
-                last_name_gender,
-                Some(&*usage.usage_code),
-                Some(1),
-                true,
-            );
-            let matched = match session.request(last_name_request) {
-                Allowed(JsonResponse::NameList(JsonNameList { names })) => {
-                    Ok(names.last().unwrap().to_owned())
-                }
-                Allowed(_) => Err("At last name request: parsing error".into()),
-                Failed(e) => Err(format!(
-                    "At last name request for usage {:?}: {:?}",
-                    usage, e
-                )),
-                Governed(_, _) => Err("At last name request: governed".into()),
-                ReqwestError(e) => Err(format!("At last name request: {}", e)),
-            };
+            let matched = _family_name(session, last_name_gender, &usage);
             match matched {
-                Ok(name) => {
-                    result = Ok(name);
+                Ok(family_name) => {
+                    result = Ok((family_name, usage));
                     break;
                 }
                 Err(e) => errs_acc.push(e),
@@ -143,8 +235,7 @@ fn _surname(session: Session, gender: Gender, first_name: String) -> Result<Name
         match result {
             Ok(r) => {
                 if !errs_acc.is_empty() {
-                    println!("SOME_ERRORS");
-                    println!("{}", errs_acc.join("\n"));
+                    warn!("Some requests failed:\n{}", errs_acc.join("\n"));
                 }
                 Ok(r)
             }
@@ -155,70 +246,276 @@ fn _surname(session: Session, gender: Gender, first_name: String) -> Result<Name
         }
     });
 
+    Ok(match last_name_result {
+        Ok((family_name, usage)) => Name {
+            first_name,
+            patronymic: family_name.patronymic,
+            last_name_result: family_name
+                .last_name
+                .ok_or_else(|| format!("{} names use no surname", usage.usage_full)),
+            second_last_name: family_name.second_last_name,
+            epithet: None,
+            usage: Some(usage),
+            given_usage: None,
+        },
+        Err(e) => Name {
+            first_name,
+            patronymic: None,
+            last_name_result: Err(e),
+            second_last_name: None,
+            epithet: None,
+            usage: None,
+            given_usage: None,
+        },
+    })
+}
+
+fn _era_name(
+    session: &BtnSession,
+    era: Era,
+    gender: Gender,
+    exclusions: &Exclusions,
+) -> Result<Name, String> {
+    let info = *era_usages(era)
+        .into_iter()
+        .filter(|info| !exclusions.excludes(&info.code))
+        .collect::<Vec<_>>()
+        .choose(&mut *session.rng())
+        .ok_or("No usages of this era are allowed")?;
+    let usage = usage_for(&info.code, gender);
+
+    let first_name = _given_name(session, gender, &info.code)?;
+    let no_surname = || Err(format!("{} names have no surname", info.name));
+
+    let (patronymic, last_name_result, epithet) = match era {
+        Era::Mythology => (None, no_surname(), None),
+        Era::Biblical => {
+            let patronymic = _patronymic(session, PatronymicStyle::Hebrew, gender, &info.code)?;
+            (Some(patronymic), no_surname(), None)
+        }
+        Era::Medieval => {
+            let last_name = usage_rules().rewrite(&info.code).map(|usage_code| {
+                let base_usage = Usage {
+                    usage_code,
+                    ..usage.clone()
+                };
+                _last_name(session, gender, &base_usage)
+            });
+            match last_name {
+                Some(Ok(last_name)) => (None, Ok(last_name), None),
+                _ => (None, no_surname(), Some(epithet(&mut *session.rng()))),
+            }
+        }
+        Era::Ancient => (None, no_surname(), Some(epithet(&mut *session.rng()))),
+    };
+
     Ok(Name {
         first_name,
+        patronymic,
         last_name_result,
+        second_last_name: None,
+        epithet,
+        usage: Some(usage),
+        given_usage: None,
     })
 }
 
-fn _name(gender_opt: Option<Gender>, mode_opt: Option<GenMode>) -> Result<Name, String> {
-    let key_string = env::var("BTN_API_KEY").unwrap();
-    let key = key_string.as_str();
-    let session = Session::new_default(key);
+/// Generate a given name from one usage and a surname from another. Without
+/// a surname usage, the surname is paired coherently with the given name.
+fn _mixed_name(
+    session: &BtnSession,
+    gender: Gender,
+    given_usage_code: Option<String>,
+    surname_usage_code: Option<String>,
+    exclusions: &Exclusions,
+) -> Result<Name, String> {
+    let (first_name, given_usage) = match given_usage_code {
+        Some(code) => {
+            let usage = usage_for(&code, gender);
+            (_given_name(session, gender, &usage.usage_code)?, usage)
+        }
+        None => {
+            // A random first name, shown with its first usage that isn't
+            // excluded, so both usages always appear.
+            let first_name = _first_name(session, gender)?;
+            let usage = _usages(session, &first_name)?
+                .into_iter()
+                .find(|usage| !exclusions.excludes(&usage.usage_code))
+                .ok_or_else(|| format!("At given name usage: none found for {}", first_name))?;
+            (first_name, usage)
+        }
+    };
 
-    let gender = gender_opt.unwrap_or(Gender::Any);
+    let name = match surname_usage_code {
+        None => _surname(session, gender, first_name, exclusions)?,
+        Some(code) => {
+            let usage = usage_for(&code, gender);
+            let family_name = _family_name(session, gender, &usage)?;
+            Name {
+                first_name,
+                patronymic: family_name.patronymic,
+                last_name_result: family_name
+                    .last_name
+                    .ok_or_else(|| format!("{} names use no surname", usage.usage_full)),
+                second_last_name: family_name.second_last_name,
+                epithet: None,
+                usage: Some(usage),
+                given_usage: None,
+            }
+        }
+    };
 
-    let mode = mode_opt.unwrap_or(GenMode::Coherent);
+    Ok(Name {
+        given_usage: Some(given_usage),
+        ..name
+    })
+}
 
-    sleep(Duration::from_millis(550));
+/// The most requests to spend re-rolling names that fail the filters or
+/// constraints; at 550ms a request, this is about 15 seconds.
+const REQUEST_BUDGET: usize = 30;
 
-    match mode {
-        GenMode::Coherent => {
-            let first_name_request = random::random_with_gender(gender);
-            let first_name = match session.request(first_name_request) {
-                Allowed(JsonResponse::NameList(JsonNameList { names })) => {
-                    Ok(names.first().unwrap().to_owned())
-                }
-                Allowed(_) => Err("At first name request: parsing issue".into()),
-                Failed(e) => Err(format!("At first name request: {:?}", e)),
-                Governed(_, _) => Err("At first name request: governed".into()),
-                ReqwestError(e) => Err(format!("At first name request: {}", e)),
-            }?;
+pub(crate) fn guild_exclusions(ctx: Context<'_>) -> Exclusions {
+    guild_settings(ctx).exclusions
+}
 
-            _surname(session, gender, first_name)
+/// Retry a generation step until it produces a name that passes the
+/// filters and constraints, within a budget of requests to BehindTheName.
+pub(crate) fn reroll(
+    session: &BtnSession,
+    mut attempt: impl FnMut() -> Result<Option<Name>, String>,
+) -> Result<Name, String> {
+    let mut tries = 0;
+    while session.requests() < REQUEST_BUDGET {
+        tries += 1;
+        if let Some(name) = attempt()? {
+            return Ok(name);
         }
-        GenMode::Chaotic => {
-            let name_request = random::random_with_params(gender, None, Some(1), true);
-            let name_vec = match session.request(name_request) {
-                Allowed(JsonResponse::NameList(JsonNameList { names })) => Ok(names),
-                Allowed(_) => Err("At first name request: parsing issue".into()),
-                Failed(e) => Err(format!("At first name request: {:?}", e)),
-                Governed(_, _) => Err("At first name request: governed".into()),
-                ReqwestError(e) => Err(format!("At first name request: {}", e)),
-            }?;
-            match name_vec.len() {
-                1..=2 => Ok(Name {
-                    first_name: name_vec.first().unwrap().to_owned(),
-                    last_name_result: name_vec
-                        .get(1)
-                        .ok_or_else(|| "At last name request: none found?".into())
-                        .cloned(),
-                }),
-                0 => Err("No name fetched".into()),
-                _ => Err("Too many names fetched".into()),
+    }
+    Err(format!(
+        "No name satisfied the filters and constraints within {} requests ({} tries)",
+        REQUEST_BUDGET, tries
+    ))
+}
+
+/// Generate a random first name with a coherent surname, or nothing if the
+/// first name only has excluded usages or the name fails the constraints.
+pub(crate) fn _coherent_name(
+    session: &BtnSession,
+    gender: Gender,
+    exclusions: &Exclusions,
+    constraints: &Constraints,
+) -> Result<Option<Name>, String> {
+    let first_name = _first_name(session, gender)?;
+    if !constraints.accepts_first_name(&first_name) {
+        return Ok(None);
+    }
+
+    let possible_usages = _usages(session, &first_name);
+    if matches!(&possible_usages, Ok(usages) if exclusions.rejects(usages)) {
+        return Ok(None);
+    }
+
+    let name = _surname_for_usages(session, gender, first_name, possible_usages, exclusions)?;
+    Ok(Some(name).filter(|name| constraints.accepts(name)))
+}
+
+fn _chaotic_name(session: &BtnSession, gender: Gender) -> Result<Name, String> {
+    let name_request = random::random_with_params(gender, None, Some(1), true);
+    let name_vec = match session.request(name_request) {
+        Allowed(JsonResponse::NameList(JsonNameList { names })) => Ok(names),
+        Allowed(_) => Err("At first name request: parsing issue".into()),
+        Failed(e) => Err(format!("At first name request: {:?}", e)),
+        Governed(_, _) => Err("At first name request: governed".into()),
+        ReqwestError(e) => Err(format!("At first name request: {}", e)),
+    }?;
+    match name_vec.len() {
+        1..=2 => Ok(Name {
+            first_name: name_vec.first().unwrap().to_owned(),
+            last_name_result: name_vec
+                .get(1)
+                .ok_or_else(|| "At last name request: none found?".into())
+                .cloned(),
+            patronymic: None,
+            second_last_name: None,
+            epithet: None,
+            usage: None,
+            given_usage: None,
+        }),
+        0 => Err("No name fetched".into()),
+        _ => Err("Too many names fetched".into()),
+    }
+}
+
+pub(crate) fn _name(options: NameOptions) -> Result<Name, String> {
+    if options.backend == Backend::Local {
+        return local::generate(&options);
+    }
+
+    let config = config();
+    if config.btn_api_key.is_empty() {
+        return Err("No BehindTheName API key is configured; use backend:local".into());
+    }
+    let session = BtnSession::new(&config.btn_api_key, config.request_interval, options.seed);
+
+    let gender = options.gender.unwrap_or(Gender::Any);
+    let exclusions = &options.exclusions;
+
+    let constraints = &options.constraints;
+    let accept = |name: Name| Ok(Some(name).filter(|name| constraints.accepts(name)));
+
+    if options.given_usage.is_some() || options.surname_usage.is_some() {
+        let choose = |spec: &Option<UsageSpec>| {
+            spec.as_ref()
+                .map(|spec| spec.choose(exclusions, &mut *session.rng()))
+                .transpose()
+        };
+        return reroll(&session, || {
+            accept(_mixed_name(
+                &session,
+                gender,
+                choose(&options.given_usage)?,
+                choose(&options.surname_usage)?,
+                exclusions,
+            )?)
+        });
+    }
+
+    let mode = options.mode.unwrap_or(GenMode::Coherent);
+    let era_name = |era: Era| {
+        reroll(&session, || {
+            accept(_era_name(&session, era, gender, exclusions)?)
+        })
+    };
+
+    match mode {
+        GenMode::Coherent => reroll(&session, || {
+            _coherent_name(&session, gender, exclusions, constraints)
+        }),
+        GenMode::Chaotic => reroll(&session, || {
+            let name = _chaotic_name(&session, gender)?;
+            if !constraints.accepts(&name) || exclusions.is_empty() {
+                return accept(name);
             }
-        }
+            match _usages(&session, &name.first_name) {
+                Ok(usages) if exclusions.rejects(&usages) => Ok(None),
+                _ => Ok(Some(name)),
+            }
+        }),
+        GenMode::Medieval => era_name(Era::Medieval),
+        GenMode::Mythology => era_name(Era::Mythology),
+        GenMode::Biblical => era_name(Era::Biblical),
+        GenMode::Ancient => era_name(Era::Ancient),
     }
 }
 
-fn _dbg_name(first_name: String, gender_opt: Option<Gender>) -> Result<Name, String> {
-    let key_string = env::var("BTN_API_KEY").unwrap();
-    let key = key_string.as_str();
-    let session = Session::new_default(key);
+pub(crate) fn _dbg_name(first_name: String, gender_opt: Option<Gender>) -> Result<Name, String> {
+    let config = config();
+    let session = BtnSession::new(&config.btn_api_key, config.request_interval, random_seed());
 
     let gender = gender_opt.unwrap_or(Gender::Any);
 
-    _surname(session, gender, first_name)
+    _surname(&session, gender, first_name, &Exclusions::default())
 }
 
 fn no_last_name(err: String) -> String {
@@ -232,7 +529,7 @@ This name is a mononym. Pretend you're Cher. Or Zeus.
     )
 }
 
-fn an_error_occurred(err: String) -> String {
+pub(crate) fn an_error_occurred(err: String) -> String {
     format!(
         "An error occurred.
 
@@ -241,6 +538,224 @@ fn an_error_occurred(err: String) -> String {
     )
 }
 
+/// A name as a message, with its parts, its usages when they differ, and the
+/// seed it was generated with, if any, so it can be generated again.
+pub(crate) fn name_message(
+    name: &Name,
+    options: FormatOptions,
+    seed: Option<u64>,
+) -> MessageContent {
+    let parts = name_parts(name, options);
+
+    let content = match &name.last_name_result {
+        Ok(_) => full_name(&parts),
+        Err(error) if name.patronymic.is_none() && name.epithet.is_none() => {
+            format!("{} {}", full_name(&parts), no_last_name(error.clone()))
+        }
+        Err(_) => full_name(&parts),
+    };
+
+    let mut fields = parts
+        .iter()
+        .map(|part| (part.label.into(), part.link(), true))
+        .collect::<Vec<_>>();
+
+    // Mixed names show both usages, even when they're the same or the
+    // surname wasn't found.
+    if let Some(given_usage) = &name.given_usage {
+        fields.push((
+            "Given Name Usage".into(),
+            given_usage.usage_full.clone(),
+            true,
+        ));
+        let surname_usage = name
+            .usage
+            .as_ref()
+            .map_or("none".into(), |usage| usage.usage_full.clone());
+        fields.push(("Surname Usage".into(), surname_usage, true));
+    }
+    if let Some(seed) = seed {
+        fields.push(("Seed".into(), seed.to_string(), true));
+    }
+
+    MessageContent {
+        content,
+        embed: Some(MessageEmbed {
+            title: "BehindTheName".into(),
+            fields,
+        }),
+    }
+}
+
+pub(crate) async fn edit_message(
+    ctx: Context<'_>,
+    working_msg: ReplyHandle<'_>,
+    message_content: MessageContent,
+) -> Result<(), Error> {
+    edit_message_with_buttons(ctx, &working_msg, message_content, &[]).await
+}
+
+/// Edit a message, adding a row of buttons given as their custom IDs and
+/// labels.
+pub(crate) async fn edit_message_with_buttons(
+    ctx: Context<'_>,
+    working_msg: &ReplyHandle<'_>,
+    message_content: MessageContent,
+    buttons: &[(String, &str)],
+) -> Result<(), Error> {
+    working_msg
+        .edit(ctx, |m| {
+            m.content(message_content.content);
+            if let Some(MessageEmbed {
+                title: t,
+                fields: f,
+            }) = message_content.embed
+            {
+                m.embed(|e| {
+                    e.title(t);
+                    e.fields(f);
+                    e
+                });
+            }
+            if !buttons.is_empty() {
+                m.components(|c| {
+                    c.create_action_row(|r| {
+                        for (custom_id, label) in buttons {
+                            r.create_button(|b| {
+                                b.custom_id(custom_id)
+                                    .label(*label)
+                                    .style(ButtonStyle::Secondary)
+                            });
+                        }
+                        r
+                    })
+                });
+            }
+            m
+        })
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn send_export(
+    ctx: Context<'_>,
+    records: &[NameRecord],
+    format: ExportFormat,
+    file_stem: &str,
+) -> Result<(), Error> {
+    let (data, extension) = export(records, format);
+
+    ctx.send(|m| {
+        m.attachment(AttachmentType::Bytes {
+            data: Cow::Owned(data.into_bytes()),
+            filename: format!("{}.{}", file_stem, extension),
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+async fn show_name(
+    ctx: Context<'_>,
+    working_msg: &ReplyHandle<'_>,
+    name: Result<Name, String>,
+    options: FormatOptions,
+    seed: Option<u64>,
+    buttons: &[(String, &str)],
+) -> Result<Name, Error> {
+    match name {
+        Ok(name) => {
+            let message = name_message(&name, options, seed);
+            edit_message_with_buttons(ctx, working_msg, message, buttons).await?;
+            Ok(name)
+        }
+        Err(e) => {
+            ctx.say(an_error_occurred(e.clone())).await?;
+            Err(e)?
+        }
+    }
+}
+
+/// Let anyone save the name shown in `reply` to their favorites, or add it
+/// to a roster, with its buttons, until they time out and are removed.
+async fn offer_buttons(
+    ctx: Context<'_>,
+    reply: &ReplyHandle<'_>,
+    name: &Name,
+    options: FormatOptions,
+) -> Result<(), Error> {
+    let message = reply.message().await?;
+    let (channel_id, message_id) = (message.channel_id, message.id);
+    // Ephemeral replies can't be edited through the channel, and the
+    // interaction token that could edit them expires with the buttons, so
+    // their buttons are left in place.
+    let ephemeral = message
+        .flags
+        .is_some_and(|flags| flags.contains(MessageFlags::EPHEMERAL));
+
+    let serenity_ctx = ctx.serenity_context().clone();
+    let storage = ctx.data().storage.clone();
+    let first_name = name.first_name.clone();
+    let record = NameRecord::new(name, options, None);
+    let (save_id, roster_id) = (save_button_id(ctx), roster_button_id(ctx));
+    let ends_at = unix_now() + BUTTON_TIMEOUT.as_secs() as i64;
+
+    tokio::spawn(async move {
+        loop {
+            let remaining = ends_at - unix_now();
+            if remaining <= 0 {
+                break;
+            }
+            let button_ids = [save_id.clone(), roster_id.clone()];
+            let interaction = CollectComponentInteraction::new(&serenity_ctx)
+                .filter(move |mci| button_ids.contains(&mci.data.custom_id))
+                .timeout(Duration::from_secs(remaining as u64))
+                .await;
+            let Some(mci) = interaction else { break };
+
+            if mci.data.custom_id == roster_id {
+                // Picking a roster may take a while, so don't hold up other
+                // presses.
+                let (serenity_ctx, storage) = (serenity_ctx.clone(), storage.clone());
+                let (first_name, record) = (first_name.clone(), record.clone());
+                tokio::spawn(async move {
+                    if let Err(e) =
+                        add_from_button(&serenity_ctx, &storage, &mci, &first_name, &record).await
+                    {
+                        error!("At adding to a roster: {}", e);
+                    }
+                });
+                continue;
+            }
+
+            let reply = save_favorite(&storage, mci.user.id, &record);
+            if let Err(e) = mci
+                .create_interaction_response(&serenity_ctx, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| d.content(reply).ephemeral(true))
+                })
+                .await
+            {
+                error!("At saving a favorite: {}", e);
+            }
+        }
+
+        if ephemeral {
+            return;
+        }
+        if let Err(e) = channel_id
+            .edit_message(&serenity_ctx, message_id, |m| m.components(|c| c))
+            .await
+        {
+            error!("At removing the name buttons: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
 /// Generate a random name, optionally with a specific gender and mode.
 ///
 /// Generate a random name, optionally with a specific gender and mode.
@@ -253,54 +768,307 @@ fn an_error_occurred(err: String) -> String {
 ///
 ///  * coherent: will attempt to generate a name with a coherent given name and surname.
 ///  * chaotic: will generate a given name and surname completely at random.
-#[poise::command(prefix_command, slash_command, broadcast_typing)]
+///  * medieval: a medieval given name with a surname or epithet.
+///  * mythology: a mythological name; these go by a single name.
+///  * biblical: a biblical given name with a patronymic (ben/bat).
+///  * ancient: an ancient given name with an epithet.
+///
+/// In coherent mode, Russian, Ukrainian and Arabic names also get a \
+/// patronymic derived from a random father's name, and Icelandic \
+/// names get a patronymic (or matronymic) instead of a surname.
+///
+/// Spanish, Catalan and Portuguese names get both a paternal and \
+/// a maternal surname; pass surname_conjunction: true to join them \
+/// with "y", "i" or "e".
+///
+/// For mixed heritage, pass given_usage and/or surname_usage \
+/// (e.g. given_usage: jap surname_usage: ita) to take each name \
+/// from a specific culture, or heritage (e.g. iri:3 ita:1) to \
+/// draw both from weighted usages. These take precedence over mode.
+///
+/// Pass exclude to keep usages or groups (medieval, mythology, \
+/// biblical, ancient) out of the name, on top of the server's \
+/// exclusions (see /exclusions). Names are re-rolled \
+/// until one passes. Chaotic surnames have no known usage and \
+/// can't be filtered.
+///
+/// Pass constraints to only accept names that meet them, e.g. \
+/// "starts:K length:-6 alliterative". The constraints are \
+/// starts:<letters>, length:<N|N-M|-M|N->, syllables:<N|N-M>, \
+/// chars:<letters>, ascii and alliterative. Names are re-rolled \
+/// until one satisfies them; restrictive constraints may not be \
+/// satisfiable within the request budget.
+///
+/// Names are shown in the order conventional for their culture, \
+/// e.g. family name first for Hungarian or Japanese names. \
+/// Pass western_order: true to always show the given name first.
+///
+/// Every name shows the seed of the bot's own random choices \
+/// (usage order, weighted usages, patronymic forms, epithets). \
+/// Pass the same seed back to repeat those choices; names drawn \
+/// from BehindTheName are still random on its side. With \
+/// backend: local, names come from the bot's own lists instead, \
+/// and the same seed always gives the same name.
+///
+/// Pass apply: true to set the name as your nickname in this \
+/// server, with a button to undo it.
+///
+/// Pass avoid_roster to never get a first name that's already on \
+/// one of this server's rosters (see /roster). Names generated in \
+/// this server recently are also re-rolled if /no_repeat is set.
+///
+/// Press ⭐ Save under the name to add it to your favorites (see \
+/// /favorites), or 📜 Add to roster to add it to a roster.
+///
+/// As a prefix command, ~name only takes gender, mode and seed.
+#[allow(clippy::too_many_arguments)]
+#[poise::command(slash_command)]
 pub(crate) async fn name(
     ctx: Context<'_>,
     #[description = "Gender of name, optional: m|f|u"] gender: Option<Gender>,
-    #[description = "Generation mode, optional: coherent|chaotic"] mode: Option<GenMode>,
+    #[description = "Generation mode, optional: coherent|chaotic|medieval|mythology|biblical|ancient"]
+    mode: Option<GenMode>,
+    #[description = "Always put the given name first, optional"] western_order: Option<bool>,
+    #[description = "Join double surnames with y/i/e, optional"] surname_conjunction: Option<bool>,
+    #[description = "Usage(s) of the given name, optional: e.g. jap or iri:3 ita"]
+    given_usage: Option<UsageSpec>,
+    #[description = "Usage(s) of the surname, optional: e.g. ita"] surname_usage: Option<UsageSpec>,
+    #[description = "Weighted usages for both names, optional: e.g. iri:3 ita:1"] heritage: Option<
+        UsageSpec,
+    >,
+    #[description = "Usages or groups to exclude, optional: e.g. mythology bibl"] exclude: Option<
+        Exclusions,
+    >,
+    #[description = "Constraints, optional: e.g. starts:K length:-6 alliterative"]
+    constraints: Option<Constraints>,
+    #[description = "Seed for the bot's random choices, optional"] seed: Option<u64>,
+    #[description = "Where names come from, optional: btn|local"] backend: Option<Backend>,
+    #[description = "Set the name as your nickname in this server, optional"] apply: Option<bool>,
+    #[description = "Don't repeat first names on this roster, optional"] avoid_roster: Option<
+        String,
+    >,
 ) -> Result<(), Error> {
-    let working_msg = ctx.say("Working...").await?;
-
-    let name = tokio::task::spawn_blocking(move || _name(gender, mode)).await?;
-
-    match name {
-        Ok(Name {
-            first_name,
-            last_name_result,
-        }) => {
-            let full_name = format!(
-                "{} {}",
-                first_name.clone(),
-                match last_name_result.clone() {
-                    Ok(last_name) => last_name,
-                    Err(error) => no_last_name(error),
-                }
-            );
-            working_msg
-                .edit(ctx, |m| {
-                    m.content(full_name).embed(|e| {
-                        e.title("BehindTheName").field(
-                            "First Name",
-                            first_name_hyperlink(&first_name),
-                            true,
-                        );
-                        if let Ok(last_name) = last_name_result {
-                            e.field("Last Name", last_name_hyperlink(&last_name), true);
-                        }
-                        e
-                    })
-                })
+    // Check the nickname can be applied before spending requests on a
+    // name, as /rename does.
+    let apply_to = if apply.unwrap_or(false) {
+        let Some(member) = ctx.author_member().await else {
+            ctx.say("Nicknames can only be applied in a server.")
                 .await?;
+            return Ok(());
+        };
+        if let Err(e) = check_renamable(ctx, &member, true).await {
+            ctx.say(e).await?;
+            return Ok(());
         }
-        Err(e) => {
-            ctx.say(an_error_occurred(e.clone())).await?;
-            Err(e)?
-        }
+        Some(member.into_owned())
+    } else {
+        None
+    };
+
+    let scope = NameScope::of(ctx);
+    let mut name_options = scope.name_options(NameArgs {
+        gender,
+        mode,
+        given_usage,
+        surname_usage,
+        heritage,
+        exclude,
+        constraints,
+        seed,
+        backend,
+    })?;
+    if let Some(roster) = avoid_roster {
+        let Some(guild_id) = ctx.guild_id() else {
+            ctx.say("Rosters can only be used in a server.").await?;
+            return Ok(());
+        };
+        let Some(roster) = find_roster(ctx, guild_id, &roster).await? else {
+            return Ok(());
+        };
+        let first_names = scope.storage.roster_first_names(roster.id)?;
+        name_options
+            .constraints
+            .avoid_first_names(first_names.iter().map(String::as_str));
+    }
+
+    let options = scope.format_options(western_order, surname_conjunction);
+
+    let name = generate_name(ctx, &scope, name_options, options).await?;
+
+    if let Some(member) = apply_to {
+        apply_nickname(ctx, member, &name, options, true).await?;
     }
 
     Ok(())
 }
 
+/// Generate a random name, optionally with a specific gender and mode.
+///
+/// The prefix form of /name: ~name [gender: m|f|u] [mode] [seed]
+///
+/// poise expands its prefix argument parser for every combination \
+/// of optional arguments, so /name's full set of options is too \
+/// many to parse as a prefix command. See /help_rnc name for what \
+/// the options do.
+#[poise::command(prefix_command, broadcast_typing, hide_in_help, rename = "name")]
+pub(crate) async fn name_prefix(
+    ctx: Context<'_>,
+    #[description = "Gender of name, optional: m|f|u"] gender: Option<Gender>,
+    #[description = "Generation mode, optional"] mode: Option<GenMode>,
+    #[description = "Seed for the bot's random choices, optional"] seed: Option<u64>,
+) -> Result<(), Error> {
+    let scope = NameScope::of(ctx);
+    let name_options = scope.name_options(NameArgs {
+        gender,
+        mode,
+        seed,
+        ..Default::default()
+    })?;
+
+    let options = scope.format_options(None, None);
+    generate_name(ctx, &scope, name_options, options).await?;
+    Ok(())
+}
+
+/// /name's options for the name itself, as passed to the slash command or
+/// parsed by `dispatch` for the interactions endpoint and the REPL.
+#[derive(Default)]
+pub(crate) struct NameArgs {
+    pub(crate) gender: Option<Gender>,
+    pub(crate) mode: Option<GenMode>,
+    pub(crate) given_usage: Option<UsageSpec>,
+    pub(crate) surname_usage: Option<UsageSpec>,
+    pub(crate) heritage: Option<UsageSpec>,
+    pub(crate) exclude: Option<Exclusions>,
+    pub(crate) constraints: Option<Constraints>,
+    pub(crate) seed: Option<u64>,
+    pub(crate) backend: Option<Backend>,
+}
+
+impl NameArgs {
+    /// The options to generate with: heritage stands in for either usage
+    /// left out, the guild's `exclusions` are added to those passed, and
+    /// `defaults` fill in the rest.
+    pub(crate) fn name_options(
+        self,
+        exclusions: &Exclusions,
+        defaults: &NameDefaults,
+    ) -> NameOptions {
+        let mut options = NameOptions {
+            gender: self.gender,
+            mode: self.mode,
+            given_usage: self.given_usage.or_else(|| self.heritage.clone()),
+            surname_usage: self.surname_usage.or(self.heritage),
+            exclusions: exclusions.union(&self.exclude.unwrap_or_default()),
+            constraints: self.constraints.unwrap_or_default(),
+            seed: self.seed.unwrap_or_else(random_seed),
+            backend: self.backend.unwrap_or_default(),
+        };
+        options.fill_defaults(defaults);
+        options
+    }
+}
+
+/// Who /name generates a name for and where: all its body needs besides
+/// the options, so the slash command, the interactions endpoint and the
+/// REPL run the same body.
+pub(crate) struct NameScope<'a> {
+    pub(crate) storage: &'a Storage,
+    pub(crate) user_id: UserId,
+    pub(crate) guild_id: Option<GuildId>,
+    settings: GuildSettings,
+    prefs: UserPrefs,
+}
+
+impl<'a> NameScope<'a> {
+    /// The scope of a user in a guild, or in DMs, under the guild's
+    /// `settings`.
+    pub(crate) fn new(
+        storage: &'a Storage,
+        user_id: UserId,
+        guild_id: Option<GuildId>,
+        settings: GuildSettings,
+    ) -> NameScope<'a> {
+        NameScope {
+            storage,
+            user_id,
+            guild_id,
+            settings,
+            prefs: stored_prefs(storage, user_id),
+        }
+    }
+
+    /// The scope of the invoking user.
+    pub(crate) fn of(ctx: Context<'a>) -> NameScope<'a> {
+        NameScope::new(
+            &ctx.data().storage,
+            ctx.author().id,
+            ctx.guild_id(),
+            guild_settings(ctx),
+        )
+    }
+
+    /// The options to generate with: `args` over the user's and the guild's
+    /// defaults, with the guild's exclusions, avoiding the guild's recent
+    /// names if /no_repeat is set.
+    pub(crate) fn name_options(&self, args: NameArgs) -> Result<NameOptions, Error> {
+        let defaults = layered_defaults(&self.prefs, self.settings.defaults.clone());
+        let mut options = args.name_options(&self.settings.exclusions, &defaults);
+        avoid_guild_repeats(self.storage, self.guild_id, &mut options.constraints)?;
+        Ok(options)
+    }
+
+    /// How to show the name, from the options passed, or else the user's
+    /// preferences.
+    pub(crate) fn format_options(
+        &self,
+        western_order: Option<bool>,
+        surname_conjunction: Option<bool>,
+    ) -> FormatOptions {
+        preferred_format(&self.prefs, western_order, surname_conjunction)
+    }
+
+    /// Generate a name and add it to the history.
+    pub(crate) async fn generate(&self, options: NameOptions) -> Result<Name, String> {
+        let mode = options.mode_label();
+        let gender = options.gender.unwrap_or(Gender::Any);
+        let name = tokio::task::spawn_blocking(move || _name(options))
+            .await
+            .map_err(|e| e.to_string())??;
+        add_to_history(
+            self.storage,
+            self.guild_id,
+            self.user_id,
+            &name,
+            mode,
+            gender,
+        );
+        Ok(name)
+    }
+}
+
+/// Generate a name and show it with buttons to save it to favorites or add
+/// it to a roster, returning it for further use.
+pub(crate) async fn generate_name(
+    ctx: Context<'_>,
+    scope: &NameScope<'_>,
+    name_options: NameOptions,
+    options: FormatOptions,
+) -> Result<Name, Error> {
+    let working_msg = ctx.say("Working...").await?;
+
+    let seed = name_options.seed;
+    let name = scope.generate(name_options).await;
+
+    let mut buttons = vec![(save_button_id(ctx), "⭐ Save")];
+    if ctx.guild_id().is_some() {
+        buttons.push((roster_button_id(ctx), "📜 Add to roster"));
+    }
+    let name = show_name(ctx, &working_msg, name, options, Some(seed), &buttons).await?;
+    offer_buttons(ctx, &working_msg, &name, options).await?;
+    Ok(name)
+}
+
 /// Generate a random name.
 ///
 /// Debug command; please ignore.
@@ -309,46 +1077,73 @@ pub(crate) async fn debug_name(
     ctx: Context<'_>,
     #[description = "First name"] first_name: String,
     #[description = "Gender of name"] gender: Option<Gender>,
+    #[description = "Always put the given name first"] western_order: Option<bool>,
+    #[description = "Join double surnames with y/i/e"] surname_conjunction: Option<bool>,
 ) -> Result<(), Error> {
     let working_msg = ctx.say("Working...").await?;
 
     let name = tokio::task::spawn_blocking(move || _dbg_name(first_name, gender)).await?;
 
-    match name {
-        Ok(Name {
-            first_name,
-            last_name_result,
-        }) => {
-            let full_name = format!(
-                "{} {}",
-                first_name.clone(),
-                match last_name_result.clone() {
-                    Ok(last_name) => last_name,
-                    Err(error) => no_last_name(error),
-                }
-            );
-            working_msg
-                .edit(ctx, |m| {
-                    m.content(full_name).embed(|e| {
-                        e.title("BehindTheName").field(
-                            "First Name",
-                            first_name_hyperlink(&first_name),
-                            true,
-                        );
-                        if let Ok(last_name) = last_name_result {
-                            e.field("Last Name", last_name_hyperlink(&last_name), true);
-                        }
-                        e
-                    })
-                })
-                .await?;
-        }
-        Err(e) => {
-            ctx.say(an_error_occurred(e.clone())).await?;
-            Err(e)?
-        }
+    let options = format_options(ctx, western_order, surname_conjunction);
+
+    show_name(ctx, &working_msg, name, options, None, &[]).await?;
+    Ok(())
+}
+
+/// Show how usage rules augment usages.
+///
+/// Debug command; please ignore.
+///
+/// Shows the usage rewrite rules in the order they're tried, and \
+/// for each usage code given (e.g. eng-medi gmc-old iri), the rule \
+/// that applies and the usages a surname would be looked up in.
+#[poise::command(prefix_command, slash_command, hide_in_help)]
+pub(crate) async fn debug_usages(
+    ctx: Context<'_>,
+    #[description = "Usage codes, separated by spaces"]
+    #[rest]
+    usages: Option<String>,
+) -> Result<(), Error> {
+    let rules = usage_rules();
+    let mut lines = vec!["Rules, in the order they're tried:".to_string()];
+    lines.extend(rules.rules.iter().map(|rule| {
+        format!(
+            "`{}` → `{}` ({:?}, priority {})",
+            rule.pattern, rule.replacement, rule.action, rule.priority
+        )
+    }));
+
+    let codes = usages.unwrap_or_default();
+    let codes = codes.split_whitespace().collect::<Vec<_>>();
+    if !codes.is_empty() {
+        lines.push(String::new());
+        lines.extend(codes.iter().map(|code| match rules.rule_for(code) {
+            Some(rule) => format!(
+                "{} → {} ({:?}, by `{}`)",
+                code,
+                rules.rewrite(code).unwrap(),
+                rule.action,
+                rule.pattern
+            ),
+            None => format!("{}: no rule", code),
+        }));
+        let augmented = rules.augment(
+            codes
+                .iter()
+                .map(|code| usage_for(code, Gender::Any))
+                .collect(),
+        );
+        lines.push(format!(
+            "Tried in order: {}",
+            augmented
+                .iter()
+                .map(|usage| usage.usage_code.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
     }
 
+    ctx.say(lines.join("\n")).await?;
     Ok(())
 }
 
@@ -367,47 +1162,71 @@ async fn get_name_vector(ctx: Context<'_>, name: Option<String>) -> Vec<String>
     }
 }
 
-struct MessageContent {
-    content: String,
-    embed: Option<MessageEmbed>,
+pub(crate) struct MessageContent {
+    pub(crate) content: String,
+    pub(crate) embed: Option<MessageEmbed>,
 }
 
-struct MessageEmbed {
-    title: String,
-    fields: Vec<(String, String, bool)>,
+pub(crate) struct MessageEmbed {
+    pub(crate) title: String,
+    pub(crate) fields: Vec<(String, String, bool)>,
 }
 
-async fn _about<'att>(names: Vec<String>) -> Result<MessageContent, Error> {
+/// Whether a BehindTheName page exists, remembering the answer for later
+/// lookups. Only a success or a 404 answers that; other errors, e.g. being
+/// rate limited, are returned and not remembered.
+async fn page_exists(clt: &Client, url: &str) -> Result<bool, reqwest::Error> {
+    let cached = PAGE_CACHE.lock().unwrap().get(url).copied();
+    if let Some(exists) = cached {
+        return Ok(exists);
+    }
+
+    let response = clt.head(url).send().await?;
+    let exists = match response.status() {
+        status if status.is_success() => true,
+        StatusCode::NOT_FOUND => false,
+        _ => return response.error_for_status().map(|_| false),
+    };
+    let max_pages = config().max_cached_pages;
+    if max_pages == 0 {
+        return Ok(exists);
+    }
+    let mut cache = PAGE_CACHE.lock().unwrap();
+    if cache.len() >= max_pages {
+        cache.clear();
+    }
+    cache.insert(url.into(), exists);
+    Ok(exists)
+}
+
+/// A name part found on BehindTheName.
+fn found_part(kind: PartKind, label: &str, text: &str, url: &str) -> PartRecord {
+    PartRecord {
+        kind,
+        label: label.into(),
+        text: text.into(),
+        url: Some(url.into()),
+    }
+}
+
+/// Look up the parts of a name on BehindTheName: every word but the last
+/// as a first name, and the last as a last name or else a first name.
+/// Returns the parts found, labeled as /about_name shows them.
+pub(crate) async fn _about(names: &[String]) -> Result<Vec<PartRecord>, Error> {
     let clt = Client::new();
 
     Ok(match names.len() {
-        0 => MessageContent {
-            content: "No name found".into(),
-            embed: None,
-        },
+        0 => vec![],
         1 => {
             let first = names.first().unwrap();
             let url = first_name_url(first);
-            let url_opt = clt
-                .head(&url)
-                .send()
+            let url_opt = page_exists(&clt, &url)
                 .await
                 .map_err(|e| format!("1: {:?}, {:?}, {}", &e, e.url(), &url))?
-                .status()
-                .is_success()
                 .then_some(url);
             match url_opt {
-                Some(url) => MessageContent {
-                    content: first.into(),
-                    embed: Some(MessageEmbed {
-                        title: "BehindTheName".into(),
-                        fields: vec![("First Name".into(), hyperlink(first, &url), true)],
-                    }),
-                },
-                None => MessageContent {
-                    content: format!("Name {} not found.", first),
-                    embed: None,
-                },
+                Some(url) => vec![found_part(PartKind::Given, "First Name", first, &url)],
+                None => vec![],
             }
         }
         _ => {
@@ -415,32 +1234,22 @@ async fn _about<'att>(names: Vec<String>) -> Result<MessageContent, Error> {
             let mut urls: Vec<Option<String>> = vec![];
             for name in names[..names.len() - 1].iter() {
                 let url = first_name_url(name);
-                let status = clt
-                    .head(&url)
-                    .send()
+                let exists = page_exists(&clt, &url)
                     .await
-                    .map_err(|e| format!("2: {:?}, {:?}, {}", &e, e.url(), &url))?
-                    .status();
-                urls.push(status.is_success().then_some(url));
+                    .map_err(|e| format!("2: {:?}, {:?}, {}", &e, e.url(), &url))?;
+                urls.push(exists.then_some(url));
             }
 
             let last_url = last_name_url(last);
-            let last_url_opt = clt
-                .head(&last_url)
-                .send()
+            let last_url_opt = page_exists(&clt, &last_url)
                 .await
                 .map_err(|e| format!("3: {:?}, {:?}, {}", &e, e.url(), &last_url))?
-                .status()
-                .is_success()
                 .then_some(last_url)
                 .or(async {
                     let last_first_url = first_name_url(last);
-                    clt.head(&last_first_url)
-                        .send()
+                    page_exists(&clt, &last_first_url)
                         .await
                         .ok()?
-                        .status()
-                        .is_success()
                         .then_some(last_first_url)
                 }
                 .await);
@@ -454,47 +1263,74 @@ async fn _about<'att>(names: Vec<String>) -> Result<MessageContent, Error> {
                 .collect();
 
             match final_urls.len() {
-                0 => MessageContent {
-                    content: "No names found.".into(),
-                    embed: None,
-                },
+                0 => vec![],
                 1 => {
                     let (name, url) = final_urls.pop().unwrap();
-                    MessageContent {
-                        content: names.join(" "),
-                        embed: Some(MessageEmbed {
-                            title: "BehindTheName".into(),
-                            fields: vec![("First Name".into(), hyperlink(name, url), true)],
-                        }),
-                    }
+                    vec![found_part(PartKind::Given, "First Name", name, url)]
                 }
                 _ => {
                     let (last_name, last_url) = final_urls.pop().unwrap();
                     let mut url_iter = final_urls.into_iter();
                     let (first_name, first_url) = url_iter.next().unwrap();
 
-                    let mut fields = vec![];
+                    let mut parts = vec![];
 
-                    fields.push(("First Name".into(), hyperlink(first_name, first_url), true));
+                    parts.push(found_part(
+                        PartKind::Given,
+                        "First Name",
+                        first_name,
+                        first_url,
+                    ));
 
                     for (name, url) in url_iter {
-                        fields.push(("Middle Name".into(), hyperlink(name, url), true));
+                        parts.push(found_part(PartKind::Given, "Middle Name", name, url));
                     }
 
-                    fields.push(("Last Name".into(), hyperlink(last_name, last_url), true));
-                    MessageContent {
-                        content: names.join(" "),
-                        embed: Some(MessageEmbed {
-                            title: "BehindTheName".into(),
-                            fields,
-                        }),
-                    }
+                    parts.push(found_part(
+                        PartKind::Family,
+                        "Last Name",
+                        last_name,
+                        last_url,
+                    ));
+                    parts
                 }
             }
         }
     })
 }
 
+pub(crate) fn about_message(names: &[String], parts: Vec<PartRecord>) -> MessageContent {
+    if names.is_empty() {
+        return MessageContent {
+            content: "No name found".into(),
+            embed: None,
+        };
+    }
+    if parts.is_empty() {
+        return MessageContent {
+            content: match names {
+                [name] => format!("Name {} not found.", name),
+                _ => "No names found.".into(),
+            },
+            embed: None,
+        };
+    }
+
+    MessageContent {
+        content: names.join(" "),
+        embed: Some(MessageEmbed {
+            title: "BehindTheName".into(),
+            fields: parts
+                .into_iter()
+                .map(|part| {
+                    let link = hyperlink(&part.text, part.url.as_deref().unwrap());
+                    (part.label, link, true)
+                })
+                .collect(),
+        }),
+    }
+}
+
 /// Get details about your nickname or a specific name.
 ///
 /// Get details about your nickname or a specific name.
@@ -519,27 +1355,9 @@ pub(crate) async fn about_name(
 
     let name_vector = get_name_vector(ctx, name).await;
 
-    let message_content = _about(name_vector).await?;
-
-    working_msg
-        .edit(ctx, |m| {
-            m.content(message_content.content);
-            if let Some(MessageEmbed {
-                title: t,
-                fields: f,
-            }) = message_content.embed
-            {
-                m.embed(|e| {
-                    e.title(t);
-                    e.fields(f);
-                    e
-                });
-            }
-            m
-        })
-        .await?;
+    let parts = _about(&name_vector).await?;
 
-    Ok(())
+    edit_message(ctx, working_msg, about_message(&name_vector, parts)).await
 }
 
 /// Show this menu
@@ -552,10 +1370,21 @@ pub(crate) async fn help_rnc(
         "\
 randomnamecord version {}
 
-Type ~help_rnc command or /help_rnc command for more info on a command.
+Type {}help_rnc command or /help_rnc command for more info on a command.
 You can edit your message to the bot and the bot will edit its response.",
-        VERSION
+        VERSION,
+        guild_settings(ctx)
+            .prefix
+            .as_deref()
+            .unwrap_or(&config().prefix)
     );
+    // ~name and /name are separate commands, and poise shows the first
+    // one's help, ~name's, so /name's is shown for slash commands here.
+    if let (Context::Application(_), Some("name")) = (ctx, command.as_deref()) {
+        let help = name().help_text.map_or_else(String::new, |help| help());
+        ctx.say(help).await?;
+        return Ok(());
+    }
     let config = poise::builtins::HelpConfiguration {
         extra_text_at_bottom: &extra_text,
         ..Default::default()
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use behindthename::types::Gender;
+use poise::serenity_prelude::{
+    ButtonStyle, CollectComponentInteraction, InteractionResponseType, Member,
+};
+
+use crate::btn::random_seed;
+use crate::commands::names::{generate_name, guild_exclusions, NameScope};
+use crate::commands::prefs::name_defaults;
+use crate::naming::format::{nickname, FormatOptions};
+use crate::resources::types::*;
+
+/// How long the undo button stays usable.
+const UNDO_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Check that the bot can change the nickname of `target`, and, for
+/// `by_self`, that the invoking member may change their own nickname.
+pub(crate) async fn check_renamable(
+    ctx: Context<'_>,
+    target: &Member,
+    by_self: bool,
+) -> Result<(), String> {
+    let guild = ctx
+        .guild()
+        .ok_or("Nicknames can only be changed in a server.")?;
+    let bot_id = ctx.framework().bot_id;
+
+    if target.user.id == guild.owner_id {
+        return Err("Nobody can change the server owner's nickname.".into());
+    }
+
+    let permissions = |user_id| {
+        let guild = &guild;
+        async move {
+            guild
+                .member_permissions(ctx, user_id)
+                .await
+                .map_err(|e| format!("Couldn't check permissions: {}", e))
+        }
+    };
+
+    if !permissions(bot_id).await?.manage_nicknames() {
+        return Err("I need the Manage Nicknames permission to change nicknames.".into());
+    }
+
+    if by_self {
+        let own = permissions(target.user.id).await?;
+        if !own.change_nickname() && !own.manage_nicknames() {
+            return Err("You don't have permission to change your nickname here.".into());
+        }
+    }
+
+    if guild.greater_member_hierarchy(ctx, bot_id, target.user.id) != Some(bot_id) {
+        return Err(format!(
+            "My highest role needs to be above {}'s to change their nickname.",
+            target.display_name()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Set a generated name as the nickname of `target`, with a button that
+/// restores the previous nickname.
+pub(crate) async fn apply_nickname(
+    ctx: Context<'_>,
+    target: Member,
+    name: &Name,
+    options: FormatOptions,
+    by_self: bool,
+) -> Result<(), Error> {
+    if let Err(e) = check_renamable(ctx, &target, by_self).await {
+        ctx.say(e).await?;
+        return Ok(());
+    }
+
+    let previous = target.nick.clone();
+    let nickname = nickname(name, options);
+    target.edit(ctx, |m| m.nickname(&nickname)).await?;
+
+    let undo_id = format!("{}undo", ctx.id());
+    let reply = ctx
+        .send(|m| {
+            m.content(format!(
+                "Changed {}'s nickname to {}.",
+                target.user.name, nickname
+            ))
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id(&undo_id)
+                            .label("Undo")
+                            .style(ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await?;
+
+    let interaction = CollectComponentInteraction::new(ctx)
+        .author_id(ctx.author().id)
+        .filter(move |mci| mci.data.custom_id == undo_id)
+        .timeout(UNDO_TIMEOUT)
+        .await;
+
+    match interaction {
+        Some(mci) => {
+            // An empty nickname resets it to the username.
+            target
+                .edit(ctx, |m| m.nickname(previous.as_deref().unwrap_or_default()))
+                .await?;
+            mci.create_interaction_response(ctx, |r| {
+                r.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| {
+                        d.content(format!("Restored {}'s nickname.", target.user.name))
+                            .components(|c| c)
+                    })
+            })
+            .await?;
+        }
+        None => {
+            reply.edit(ctx, |m| m.components(|c| c)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Give a member a random nickname.
+///
+/// Give a member a random nickname.
+///
+/// Generates a name as /name does and sets it as the member's \
+/// nickname, shortened to fit Discord's 32-character limit if \
+/// needed. Requires Manage Nicknames, and the bot's highest role \
+/// must be above the member's. The Undo button restores the \
+/// previous nickname.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_NICKNAMES"
+)]
+pub(crate) async fn rename(
+    ctx: Context<'_>,
+    #[description = "Member to rename"] member: Member,
+    #[description = "Gender of name, optional: m|f|u"] gender: Option<Gender>,
+    #[description = "Generation mode, optional"] mode: Option<GenMode>,
+) -> Result<(), Error> {
+    if let Err(e) = check_renamable(ctx, &member, false).await {
+        ctx.say(e).await?;
+        return Ok(());
+    }
+
+    let mut name_options = NameOptions {
+        gender,
+        mode,
+        exclusions: guild_exclusions(ctx),
+        seed: random_seed(),
+        ..Default::default()
+    };
+    name_options.fill_defaults(&name_defaults(ctx));
+
+    let options = FormatOptions::default();
+    let name = generate_name(ctx, &NameScope::of(ctx), name_options, options).await?;
+
+    apply_nickname(ctx, member, &name, options, false).await
+}
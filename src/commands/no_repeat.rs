@@ -0,0 +1,262 @@
+use poise::serenity_prelude::GuildId;
+
+use crate::naming::constraints::Constraints;
+use crate::resources::types::*;
+use crate::storage::history::HistoryFilter;
+use crate::storage::no_repeat::NoRepeat;
+use crate::storage::{unix_now, Storage};
+
+const MAX_NAMES: u32 = 1000;
+const MAX_DAYS: u32 = 365;
+/// The most history entries to check, however many days the window covers.
+const MAX_ENTRIES: usize = 10_000;
+
+/// Reject names generated in a guild within its no-repeat window, or
+/// nothing in DMs.
+pub(crate) fn avoid_guild_repeats(
+    storage: &Storage,
+    guild_id: Option<GuildId>,
+    constraints: &mut Constraints,
+) -> Result<(), Error> {
+    let Some(guild_id) = guild_id else {
+        return Ok(());
+    };
+    let Some(no_repeat) = storage.no_repeat(guild_id)? else {
+        return Ok(());
+    };
+
+    let mut entries = vec![];
+    if let Some(names) = no_repeat.names {
+        let filter = HistoryFilter {
+            guild_id: Some(guild_id),
+            ..Default::default()
+        };
+        entries.extend(storage.history(&filter, names as usize)?);
+    }
+    if let Some(days) = no_repeat.days {
+        let filter = HistoryFilter {
+            guild_id: Some(guild_id),
+            since: Some(unix_now() - i64::from(days) * 24 * 60 * 60),
+            ..Default::default()
+        };
+        entries.extend(storage.history(&filter, MAX_ENTRIES)?);
+    }
+
+    if no_repeat.full_names {
+        constraints.avoid_full_names(entries.iter().map(|entry| entry.full_name.as_str()));
+    } else {
+        constraints.avoid_first_names(entries.iter().map(|entry| entry.first_name.as_str()));
+    }
+    Ok(())
+}
+
+fn describe(no_repeat: &NoRepeat) -> String {
+    let window = match (no_repeat.names, no_repeat.days) {
+        (Some(names), Some(days)) => format!("the last {} names or {} days", names, days),
+        (Some(names), None) => format!("the last {} names", names),
+        (None, Some(days)) => format!("the last {} days", days),
+        (None, None) => "no names".into(),
+    };
+    format!(
+        "/name won't repeat a {} generated in this server within {}.",
+        if no_repeat.full_names {
+            "full name"
+        } else {
+            "first name"
+        },
+        window
+    )
+}
+
+/// Keep /name from repeating names in this server.
+///
+/// Keep /name from repeating names in this server.
+///
+/// /no_repeat set makes /name re-roll any first name generated in \
+/// this server within the last names names or days days, or both. \
+/// Pass full_names: true to only re-roll repeated full names.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("no_repeat_set", "no_repeat_show", "no_repeat_off")
+)]
+pub(crate) async fn no_repeat(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set how far back /name avoids repeating names.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "set")]
+pub(crate) async fn no_repeat_set(
+    ctx: Context<'_>,
+    #[description = "Don't repeat the last this many names, optional"] names: Option<u32>,
+    #[description = "Don't repeat names from the last this many days, optional"] days: Option<u32>,
+    #[description = "Compare full names instead of first names, optional"] full_names: Option<bool>,
+) -> Result<(), Error> {
+    if names.is_none() && days.is_none() {
+        ctx.say("Pass names, days, or both.").await?;
+        return Ok(());
+    }
+
+    let no_repeat = NoRepeat {
+        names: names.map(|names| names.clamp(1, MAX_NAMES)),
+        days: days.map(|days| days.clamp(1, MAX_DAYS)),
+        full_names: full_names.unwrap_or(false),
+    };
+    ctx.data()
+        .storage
+        .set_no_repeat(ctx.guild_id().unwrap(), &no_repeat)?;
+
+    ctx.say(describe(&no_repeat)).await?;
+    Ok(())
+}
+
+/// Show how far back /name avoids repeating names.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "show")]
+pub(crate) async fn no_repeat_show(ctx: Context<'_>) -> Result<(), Error> {
+    let message = match ctx.data().storage.no_repeat(ctx.guild_id().unwrap())? {
+        Some(no_repeat) => describe(&no_repeat),
+        None => "/name may repeat names in this server.".into(),
+    };
+
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Let /name repeat names again.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "off")]
+pub(crate) async fn no_repeat_off(ctx: Context<'_>) -> Result<(), Error> {
+    let message = if ctx
+        .data()
+        .storage
+        .remove_no_repeat(ctx.guild_id().unwrap())?
+    {
+        "/name may repeat names in this server again."
+    } else {
+        "/name already may repeat names in this server."
+    };
+
+    ctx.say(message).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use poise::serenity_prelude::UserId;
+
+    use super::*;
+    use crate::storage::history::HistoryEntry;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn storage() -> Storage {
+        let storage = Storage::open(":memory:").unwrap();
+        let now = unix_now();
+        for (guild_id, first_name, last_name, created_at) in [
+            (1, "Ada", "Lovelace", now - 10 * DAY),
+            (1, "Grace", "Hopper", now - 3 * DAY),
+            (1, "Alan", "Turing", now - DAY),
+            (1, "Émile", "Zola", now),
+            (2, "Marie", "Curie", now),
+        ] {
+            storage
+                .add_history(&HistoryEntry {
+                    guild_id: Some(GuildId(guild_id)),
+                    user_id: UserId(10),
+                    first_name: first_name.into(),
+                    last_name: Some(last_name.into()),
+                    full_name: format!("{} {}", first_name, last_name),
+                    usage: None,
+                    given_usage: None,
+                    mode: "coherent".into(),
+                    gender: String::new(),
+                    created_at,
+                })
+                .unwrap();
+        }
+        storage
+    }
+
+    fn name(first_name: &str, last_name: &str) -> Name {
+        Name {
+            first_name: first_name.into(),
+            patronymic: None,
+            last_name_result: Ok(last_name.into()),
+            second_last_name: None,
+            epithet: None,
+            usage: None,
+            given_usage: None,
+        }
+    }
+
+    /// The first names of guild 1's history /name would re-roll under a
+    /// no-repeat window.
+    fn avoided(storage: &Storage, no_repeat: Option<NoRepeat>) -> Vec<&'static str> {
+        let guild_id = GuildId(1);
+        match no_repeat {
+            Some(no_repeat) => storage.set_no_repeat(guild_id, &no_repeat).unwrap(),
+            None => {
+                storage.remove_no_repeat(guild_id).unwrap();
+            }
+        }
+        let mut constraints = Constraints::default();
+        avoid_guild_repeats(storage, Some(guild_id), &mut constraints).unwrap();
+        ["Ada", "Grace", "Alan", "emile", "Marie"]
+            .into_iter()
+            .filter(|first_name| !constraints.accepts_first_name(first_name))
+            .collect()
+    }
+
+    #[test]
+    fn avoids_names_within_the_window() {
+        let storage = storage();
+        let window = |names, days| NoRepeat {
+            names,
+            days,
+            full_names: false,
+        };
+        assert!(avoided(&storage, None).is_empty());
+        assert_eq!(
+            avoided(&storage, Some(window(Some(2), None))),
+            ["Alan", "emile"]
+        );
+        assert_eq!(
+            avoided(&storage, Some(window(None, Some(5)))),
+            ["Grace", "Alan", "emile"]
+        );
+        assert_eq!(
+            avoided(&storage, Some(window(Some(1), Some(2)))),
+            ["Alan", "emile"]
+        );
+    }
+
+    #[test]
+    fn avoids_full_names_when_asked() {
+        let storage = storage();
+        storage
+            .set_no_repeat(
+                GuildId(1),
+                &NoRepeat {
+                    names: None,
+                    days: Some(5),
+                    full_names: true,
+                },
+            )
+            .unwrap();
+        let mut constraints = Constraints::default();
+        avoid_guild_repeats(&storage, Some(GuildId(1)), &mut constraints).unwrap();
+        assert!(constraints.accepts_first_name("Grace"));
+        assert!(!constraints.accepts(&name("Grace", "Hopper")));
+        assert!(constraints.accepts(&name("Grace", "Kelly")));
+        assert!(constraints.accepts(&name("Ada", "Lovelace")));
+    }
+
+    #[test]
+    fn avoids_nothing_in_dms() {
+        let storage = storage();
+        let mut constraints = Constraints::default();
+        avoid_guild_repeats(&storage, None, &mut constraints).unwrap();
+        assert!(constraints.accepts(&name("Émile", "Zola")));
+    }
+}
@@ -0,0 +1,159 @@
+use behindthename::types::Gender;
+use poise::serenity_prelude::UserId;
+use tracing::warn;
+
+use crate::commands::settings::guild_settings;
+use crate::config::config;
+use crate::naming::format::FormatOptions;
+use crate::resources::types::*;
+use crate::storage::prefs::UserPrefs;
+use crate::storage::Storage;
+
+/// A user's preferences. Preferences that can't be read aren't worth
+/// failing the command over, so errors are only logged.
+pub(crate) fn stored_prefs(storage: &Storage, user_id: UserId) -> UserPrefs {
+    storage.user_prefs(user_id).unwrap_or_else(|e| {
+        warn!("At loading preferences of {}: {}", user_id, e);
+        UserPrefs::default()
+    })
+}
+
+/// The invoking user's preferences.
+fn user_prefs(ctx: Context<'_>) -> UserPrefs {
+    stored_prefs(&ctx.data().storage, ctx.author().id)
+}
+
+/// The defaults for options a name command leaves unset: the user's
+/// preferences, then the guild's settings, then the bot's configuration.
+pub(crate) fn name_defaults(ctx: Context<'_>) -> NameDefaults {
+    layered_defaults(&user_prefs(ctx), guild_settings(ctx).defaults)
+}
+
+/// `name_defaults` from a user's preferences and a guild's defaults.
+pub(crate) fn layered_defaults(prefs: &UserPrefs, guild_defaults: NameDefaults) -> NameDefaults {
+    prefs
+        .defaults
+        .clone()
+        .or(guild_defaults)
+        .or(config().defaults.clone())
+}
+
+/// How to show a name, from the options passed, or else the user's
+/// preferences.
+pub(crate) fn format_options(
+    ctx: Context<'_>,
+    western_order: Option<bool>,
+    surname_conjunction: Option<bool>,
+) -> FormatOptions {
+    preferred_format(&user_prefs(ctx), western_order, surname_conjunction)
+}
+
+/// `format_options` from a user's preferences.
+pub(crate) fn preferred_format(
+    prefs: &UserPrefs,
+    western_order: Option<bool>,
+    surname_conjunction: Option<bool>,
+) -> FormatOptions {
+    FormatOptions {
+        western_order: western_order.or(prefs.western_order).unwrap_or(false),
+        surname_conjunction: surname_conjunction
+            .or(prefs.surname_conjunction)
+            .unwrap_or(false),
+    }
+}
+
+fn describe(prefs: &UserPrefs) -> String {
+    let or_unset = |value: Option<String>| value.unwrap_or_else(|| "not set".into());
+    let defaults = &prefs.defaults;
+    [
+        format!(
+            "Gender: {}",
+            or_unset(defaults.gender.map(|gender| gender.to_string()))
+        ),
+        format!(
+            "Mode: {}",
+            or_unset(defaults.mode.map(|mode| mode.to_string()))
+        ),
+        format!(
+            "Usages: {}",
+            or_unset(defaults.usages.as_ref().map(|usages| usages.to_string()))
+        ),
+        format!(
+            "Western order: {}",
+            or_unset(prefs.western_order.map(|on| on.to_string()))
+        ),
+        format!(
+            "Surname conjunction: {}",
+            or_unset(prefs.surname_conjunction.map(|on| on.to_string()))
+        ),
+    ]
+    .join("\n")
+}
+
+/// Manage your own defaults.
+///
+/// Manage your own defaults.
+///
+/// /rnc_prefs set stores defaults for the options you leave out of \
+/// /name and /rename: gender, mode, usages (e.g. iri or jap:3 kor), \
+/// and how names are shown (western_order, surname_conjunction). \
+/// Options you pass take precedence over your defaults, which take \
+/// precedence over the server's (see /rnc_settings). A mode and \
+/// usages are one choice: setting either overrides both of the \
+/// server's. /family only uses western_order and \
+/// surname_conjunction, and /another_life uses none of them.
+///
+/// Only the options you pass are changed; /rnc_prefs reset clears \
+/// them all.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    subcommands("prefs_show", "prefs_set", "prefs_reset")
+)]
+pub(crate) async fn rnc_prefs(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show your defaults.
+#[poise::command(slash_command, prefix_command, ephemeral, rename = "show")]
+pub(crate) async fn prefs_show(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say(describe(&user_prefs(ctx))).await?;
+    Ok(())
+}
+
+/// Set some of your defaults.
+#[poise::command(slash_command, prefix_command, ephemeral, rename = "set")]
+pub(crate) async fn prefs_set(
+    ctx: Context<'_>,
+    #[description = "Default gender, optional: m|f|u"] gender: Option<Gender>,
+    #[description = "Default mode, optional"] mode: Option<GenMode>,
+    #[description = "Default usages, optional: e.g. iri or jap:3 kor"] usages: Option<UsageSpec>,
+    #[description = "Always put the given name first, optional"] western_order: Option<bool>,
+    #[description = "Join double surnames with y/i/e, optional"] surname_conjunction: Option<bool>,
+) -> Result<(), Error> {
+    let prefs = ctx
+        .data()
+        .storage
+        .update_user_prefs(ctx.author().id, |prefs| {
+            let defaults = &mut prefs.defaults;
+            defaults.gender = gender.or(defaults.gender);
+            if mode.is_some() || usages.is_some() {
+                defaults.mode = mode;
+                defaults.usages = usages;
+            }
+            prefs.western_order = western_order.or(prefs.western_order);
+            prefs.surname_conjunction = surname_conjunction.or(prefs.surname_conjunction);
+        })?;
+
+    ctx.say(describe(&prefs)).await?;
+    Ok(())
+}
+
+/// Clear all your defaults.
+#[poise::command(slash_command, prefix_command, ephemeral, rename = "reset")]
+pub(crate) async fn prefs_reset(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.data().storage.remove_user_prefs(ctx.author().id)?;
+
+    ctx.say(describe(&UserPrefs::default())).await?;
+    Ok(())
+}
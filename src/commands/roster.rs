@@ -0,0 +1,429 @@
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    self as serenity, CollectComponentInteraction, GuildId, InteractionResponseType,
+    MessageComponentInteraction, UserId,
+};
+
+use crate::commands::names::send_export;
+use crate::naming::export::NameRecord;
+use crate::resources::types::*;
+use crate::storage::rosters::{Roster, RosterEntry};
+use crate::storage::{unix_now, Storage};
+
+/// A select menu holds at most 25 options, one per roster.
+const MAX_ROSTERS: usize = 25;
+const MAX_ENTRIES: usize = 500;
+const MAX_ROSTER_NAME_LENGTH: usize = 50;
+/// How long to wait for a roster to be picked from the menu.
+const PICK_TIMEOUT: Duration = Duration::from_secs(60);
+const PAGE_SIZE: usize = 10;
+
+pub(crate) fn roster_button_id(ctx: Context<'_>) -> String {
+    format!("{}roster", ctx.id())
+}
+
+/// Add a name to a roster, returning the reply to show.
+fn add_entry(
+    storage: &Storage,
+    roster: &Roster,
+    first_name: &str,
+    record: &NameRecord,
+    added_by: UserId,
+) -> Result<String, Error> {
+    if storage.roster_entry_count(roster.id)? >= MAX_ENTRIES {
+        return Ok(format!(
+            "{} already has {} names; remove some with /roster remove.",
+            roster.name, MAX_ENTRIES
+        ));
+    }
+
+    Ok(
+        if storage.add_roster_entry(roster.id, first_name, record, added_by, unix_now())? {
+            format!("Added {} to {}.", record.full_name, roster.name)
+        } else {
+            format!("{} is already on {}.", record.full_name, roster.name)
+        },
+    )
+}
+
+/// Find a guild's roster by name, or say it doesn't exist.
+pub(crate) async fn find_roster(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    name: &str,
+) -> Result<Option<Roster>, Error> {
+    let roster = ctx.data().storage.roster(guild_id, name)?;
+    if roster.is_none() {
+        ctx.say(format!(
+            "There's no roster named {} here; see /roster list.",
+            name
+        ))
+        .await?;
+    }
+    Ok(roster)
+}
+
+/// Add a generated name to one of the guild's rosters for whoever pressed
+/// its button, asking which roster when there are several.
+pub(crate) async fn add_from_button(
+    ctx: &serenity::Context,
+    storage: &Storage,
+    mci: &MessageComponentInteraction,
+    first_name: &str,
+    record: &NameRecord,
+) -> Result<(), Error> {
+    let respond = |content: String| {
+        mci.create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(content).ephemeral(true))
+        })
+    };
+
+    let Some(guild_id) = mci.guild_id else {
+        return Ok(());
+    };
+    let mut rosters = storage
+        .rosters(guild_id)?
+        .into_iter()
+        .map(|(roster, _)| roster)
+        .collect::<Vec<_>>();
+
+    if rosters.is_empty() {
+        respond("This server has no rosters yet; create one with /roster create.".into()).await?;
+        return Ok(());
+    }
+    if rosters.len() == 1 {
+        let reply = add_entry(storage, &rosters[0], first_name, record, mci.user.id)?;
+        respond(reply).await?;
+        return Ok(());
+    }
+
+    let pick_id = format!("{}pick", mci.id);
+    mci.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::ChannelMessageWithSource)
+            .interaction_response_data(|d| {
+                d.content(format!("Add {} to which roster?", record.full_name))
+                    .ephemeral(true)
+                    .components(|c| {
+                        c.create_action_row(|row| {
+                            row.create_select_menu(|menu| {
+                                menu.custom_id(&pick_id).placeholder("Roster").options(|o| {
+                                    for roster in &rosters {
+                                        o.create_option(|option| {
+                                            option.label(&roster.name).value(roster.id)
+                                        });
+                                    }
+                                    o
+                                })
+                            })
+                        })
+                    })
+            })
+    })
+    .await?;
+
+    let picked = CollectComponentInteraction::new(ctx)
+        .author_id(mci.user.id)
+        .filter(move |pick| pick.data.custom_id == pick_id)
+        .timeout(PICK_TIMEOUT)
+        .await;
+
+    let Some(pick) = picked else {
+        mci.edit_original_interaction_response(ctx, |m| {
+            m.content("No roster picked.").components(|c| c)
+        })
+        .await?;
+        return Ok(());
+    };
+
+    let picked_id = pick
+        .data
+        .values
+        .first()
+        .and_then(|id| id.parse::<i64>().ok());
+    let reply = match rosters
+        .iter()
+        .position(|roster| Some(roster.id) == picked_id)
+    {
+        Some(index) => add_entry(
+            storage,
+            &rosters.swap_remove(index),
+            first_name,
+            record,
+            pick.user.id,
+        )?,
+        None => "That roster no longer exists.".into(),
+    };
+    pick.create_interaction_response(ctx, |r| {
+        r.kind(InteractionResponseType::UpdateMessage)
+            .interaction_response_data(|d| d.content(reply).components(|c| c))
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// A roster entry as listed, numbered from 1 within its roster.
+fn describe(number: usize, entry: &RosterEntry) -> String {
+    let mut line = format!("`{}` {}", number, entry.record.links());
+    if let Some(usages) = entry.record.usages() {
+        line.push_str(&format!(" ({})", usages));
+    }
+    if let Some(notes) = &entry.record.notes {
+        line.push_str(&format!(": {}", notes));
+    }
+    line
+}
+
+/// Keep shared lists of names, e.g. a campaign's NPCs.
+///
+/// Keep shared lists of names, e.g. a campaign's NPCs.
+///
+/// /roster create makes a roster for this server. Names are added \
+/// with the 📜 Add to roster button under a generated name, or by \
+/// hand with /roster add, optionally with notes. /roster list \
+/// lists the rosters, or a roster's names with their numbers; \
+/// /roster remove takes a name off by number, and /roster export \
+/// sends a roster as a text, CSV or JSON file.
+///
+/// Pass avoid_roster to /name to never get a first name that's \
+/// already on a roster.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    subcommands(
+        "roster_create",
+        "roster_delete",
+        "roster_add",
+        "roster_list",
+        "roster_remove",
+        "roster_export"
+    )
+)]
+pub(crate) async fn roster(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Create a roster.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "create")]
+pub(crate) async fn roster_create(
+    ctx: Context<'_>,
+    #[description = "Name of the roster, e.g. Ravenloft NPCs"]
+    #[rest]
+    name: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let storage = &ctx.data().storage;
+    let name = name.trim();
+
+    let message = if name.is_empty() || name.chars().count() > MAX_ROSTER_NAME_LENGTH {
+        format!(
+            "Roster names must be 1 to {} characters long.",
+            MAX_ROSTER_NAME_LENGTH
+        )
+    } else if storage.rosters(guild_id)?.len() >= MAX_ROSTERS {
+        format!(
+            "This server already has {} rosters; delete one with /roster delete.",
+            MAX_ROSTERS
+        )
+    } else if storage.create_roster(guild_id, name, ctx.author().id, unix_now())? {
+        format!("Created the roster {}.", name)
+    } else {
+        format!("There's already a roster named {} here.", name)
+    };
+
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Delete a roster and all its names.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "delete")]
+pub(crate) async fn roster_delete(
+    ctx: Context<'_>,
+    #[description = "Name of the roster"]
+    #[rest]
+    roster: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let Some(roster) = find_roster(ctx, guild_id, &roster).await? else {
+        return Ok(());
+    };
+
+    let can_manage = match ctx.author_member().await {
+        Some(member) => member.permissions(ctx)?.manage_guild(),
+        None => false,
+    };
+    if roster.created_by != ctx.author().id && !can_manage {
+        ctx.say("Only the roster's creator or someone with Manage Server can delete it.")
+            .await?;
+        return Ok(());
+    }
+
+    ctx.data().storage.delete_roster(roster.id)?;
+    ctx.say(format!("Deleted the roster {}.", roster.name))
+        .await?;
+    Ok(())
+}
+
+/// Add a name to a roster by hand.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "add")]
+pub(crate) async fn roster_add(
+    ctx: Context<'_>,
+    #[description = "Name of the roster"] roster: String,
+    #[description = "Name to add, given name first"] name: String,
+    #[description = "Notes, optional: e.g. innkeeper in Barovia"] notes: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let Some(roster) = find_roster(ctx, guild_id, &roster).await? else {
+        return Ok(());
+    };
+
+    let record = NameRecord {
+        notes,
+        ..NameRecord::typed(&name)
+    };
+    let Some(first_name) = name.split_whitespace().next() else {
+        ctx.say("The name can't be empty.").await?;
+        return Ok(());
+    };
+
+    let message = add_entry(
+        &ctx.data().storage,
+        &roster,
+        first_name,
+        &record,
+        ctx.author().id,
+    )?;
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// List this server's rosters, or the names on one.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "list")]
+pub(crate) async fn roster_list(
+    ctx: Context<'_>,
+    #[description = "Name of the roster, optional"]
+    #[rest]
+    roster: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let storage = &ctx.data().storage;
+
+    let Some(roster) = roster else {
+        let rosters = storage.rosters(guild_id)?;
+        let message = if rosters.is_empty() {
+            "This server has no rosters yet; create one with /roster create.".into()
+        } else {
+            rosters
+                .iter()
+                .map(|(roster, count)| {
+                    format!(
+                        "**{}**: {} names, by <@{}>",
+                        roster.name, count, roster.created_by
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        ctx.say(message).await?;
+        return Ok(());
+    };
+
+    let Some(roster) = find_roster(ctx, guild_id, &roster).await? else {
+        return Ok(());
+    };
+    let entries = storage.roster_entries(roster.id)?;
+    if entries.is_empty() {
+        ctx.say(format!("{} has no names yet.", roster.name))
+            .await?;
+        return Ok(());
+    }
+
+    let lines = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| describe(index + 1, entry))
+        .collect::<Vec<_>>();
+    let pages = lines
+        .chunks(PAGE_SIZE)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let lines = chunk.join("\n");
+            format!(
+                "**{}**\n{}\n\nPage {} of {}",
+                roster.name,
+                lines,
+                index + 1,
+                entries.len().div_ceil(PAGE_SIZE)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    poise::builtins::paginate(ctx, &pages.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+    Ok(())
+}
+
+/// Take a name off a roster.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "remove")]
+pub(crate) async fn roster_remove(
+    ctx: Context<'_>,
+    #[description = "Name of the roster"] roster: String,
+    #[description = "Number of the name, as shown by /roster list"] number: u32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let Some(roster) = find_roster(ctx, guild_id, &roster).await? else {
+        return Ok(());
+    };
+
+    let storage = &ctx.data().storage;
+    let entries = storage.roster_entries(roster.id)?;
+    let entry = (number as usize)
+        .checked_sub(1)
+        .and_then(|index| entries.get(index));
+
+    let message = match entry {
+        Some(entry) if storage.remove_roster_entry(roster.id, entry.id)? => {
+            format!("Removed {} from {}.", entry.record.full_name, roster.name)
+        }
+        _ => format!("{} has no name numbered {}.", roster.name, number),
+    };
+
+    ctx.say(message).await?;
+    Ok(())
+}
+
+/// Export a roster as a file.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "export")]
+pub(crate) async fn roster_export(
+    ctx: Context<'_>,
+    #[description = "Name of the roster"] roster: String,
+    #[description = "File format, optional: text|csv|json (default text)"] format: Option<
+        ExportFormat,
+    >,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let Some(roster) = find_roster(ctx, guild_id, &roster).await? else {
+        return Ok(());
+    };
+
+    let entries = ctx.data().storage.roster_entries(roster.id)?;
+    if entries.is_empty() {
+        ctx.say(format!("{} has no names yet.", roster.name))
+            .await?;
+        return Ok(());
+    }
+
+    let records = entries
+        .into_iter()
+        .map(|entry| entry.record)
+        .collect::<Vec<_>>();
+    send_export(
+        ctx,
+        &records,
+        format.unwrap_or(ExportFormat::Text),
+        &roster.name.replace(|c: char| !c.is_alphanumeric(), "_"),
+    )
+    .await
+}
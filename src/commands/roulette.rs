@@ -0,0 +1,328 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    ButtonStyle, CollectComponentInteraction, GuildId, Http, InteractionResponseType, UserId,
+};
+use tracing::error;
+
+use crate::btn::random_seed;
+use crate::commands::names::_name;
+use crate::commands::nickname::check_renamable;
+use crate::commands::settings::guild_settings;
+use crate::config::config;
+use crate::naming::format::{nickname, FormatOptions};
+use crate::resources::types::*;
+use crate::storage::roulette::Roulette;
+use crate::storage::{unix_now, Storage};
+
+const DEFAULT_DURATION_MINUTES: u32 = 60;
+const MAX_DURATION_MINUTES: u32 = 7 * 24 * 60;
+const DEFAULT_SIGNUP_MINUTES: u32 = 2;
+const MAX_SIGNUP_MINUTES: u32 = 30;
+/// Each participant's name takes a few seconds to generate.
+const MAX_PARTICIPANTS: usize = 25;
+
+/// Restore the original nicknames of a guild's roulette participants and
+/// forget the roulette, returning how many nicknames were restored.
+pub(crate) async fn restore_roulette(
+    http: &Http,
+    storage: &Storage,
+    guild_id: GuildId,
+) -> Result<usize, Error> {
+    let mut restored = 0;
+    for (user_id, original_nickname) in storage.roulette_nicknames(guild_id)? {
+        // An empty nickname resets it to the username.
+        let nickname = original_nickname.unwrap_or_default();
+        match guild_id
+            .edit_member(http, user_id, |m| m.nickname(nickname))
+            .await
+        {
+            Ok(_) => restored += 1,
+            Err(e) => error!("At restoring nickname of {}: {}", user_id, e),
+        }
+    }
+    storage.end_roulette(guild_id)?;
+    Ok(restored)
+}
+
+/// Restore a roulette's nicknames when it ends.
+pub(crate) fn schedule_restore(http: Arc<Http>, storage: Arc<Storage>, roulette: Roulette) {
+    tokio::spawn(async move {
+        let delay = (roulette.ends_at - unix_now()).max(0) as u64;
+        tokio::time::sleep(Duration::from_secs(delay)).await;
+
+        // The roulette may have been ended early, and another started since.
+        match storage.roulette_running(&roulette) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => return error!("At scheduled roulette end: {}", e),
+        }
+
+        let message = match restore_roulette(&http, &storage, roulette.guild_id).await {
+            Ok(restored) => format!(
+                "The nickname roulette is over! Restored {} nicknames.",
+                restored
+            ),
+            Err(e) => format!(
+                "The nickname roulette is over, but an error occurred: {}",
+                e
+            ),
+        };
+        if let Err(e) = roulette.channel_id.say(&http, message).await {
+            error!("At announcing roulette end: {}", e);
+        }
+    });
+}
+
+/// Schedule the end of every roulette still running, e.g. after a restart.
+/// Roulettes that ended while the bot was down are ended right away.
+pub(crate) fn resume_roulettes(http: Arc<Http>, storage: Arc<Storage>) -> Result<(), Error> {
+    for roulette in storage.roulettes()? {
+        schedule_restore(http.clone(), storage.clone(), roulette);
+    }
+    Ok(())
+}
+
+/// Give everyone who joins a random nickname for a while.
+///
+/// Give everyone who joins a random nickname for a while.
+///
+/// /nickname_roulette start opens signups: members join with the \
+/// button. When signups close, each participant gets a random \
+/// nickname, optionally from a theme of usages (e.g. jap or \
+/// iri:3 sco). Original nicknames are restored when the roulette \
+/// ends, even if the bot restarts in between.
+///
+/// /nickname_roulette end ends the roulette early.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_NICKNAMES",
+    subcommands("roulette_start", "roulette_end")
+)]
+pub(crate) async fn nickname_roulette(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Open signups for a nickname roulette.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "start")]
+pub(crate) async fn roulette_start(
+    ctx: Context<'_>,
+    #[description = "Minutes the nicknames last, optional (default 60)"] duration: Option<u32>,
+    #[description = "Minutes to sign up, optional (default 2)"] signup: Option<u32>,
+    #[description = "Usages of the names, optional: e.g. jap or iri:3 sco"] theme: Option<
+        UsageSpec,
+    >,
+    #[description = "Generation mode, optional"] mode: Option<GenMode>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let storage = ctx.data().storage.clone();
+
+    if storage.roulette(guild_id)?.is_some() {
+        ctx.say("A nickname roulette is already running here.")
+            .await?;
+        return Ok(());
+    }
+
+    let duration = duration
+        .unwrap_or(DEFAULT_DURATION_MINUTES)
+        .clamp(1, MAX_DURATION_MINUTES);
+    let signup = signup
+        .unwrap_or(DEFAULT_SIGNUP_MINUTES)
+        .clamp(1, MAX_SIGNUP_MINUTES);
+    let signup_ends_at = unix_now() + i64::from(signup) * 60;
+
+    let announcement = |joined: usize| {
+        format!(
+            "Nickname roulette! Join before <t:{}:R> to get a random nickname for {} minutes. \
+             Press again to leave. {} joined so far.",
+            signup_ends_at, duration, joined
+        )
+    };
+
+    let join_id = format!("{}join", ctx.id());
+    let reply = ctx
+        .send(|m| {
+            m.content(announcement(0)).components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id(&join_id)
+                            .label("Join")
+                            .style(ButtonStyle::Primary)
+                    })
+                })
+            })
+        })
+        .await?;
+
+    let mut participants: Vec<UserId> = vec![];
+    loop {
+        let remaining = signup_ends_at - unix_now();
+        if remaining <= 0 {
+            break;
+        }
+        let join_id = join_id.clone();
+        let interaction = CollectComponentInteraction::new(ctx)
+            .filter(move |mci| mci.data.custom_id == join_id)
+            .timeout(Duration::from_secs(remaining as u64))
+            .await;
+        let Some(mci) = interaction else { break };
+
+        let user_id = mci.user.id;
+        match participants.iter().position(|id| *id == user_id) {
+            Some(index) => {
+                participants.remove(index);
+            }
+            None if participants.len() < MAX_PARTICIPANTS => participants.push(user_id),
+            None => {}
+        }
+        mci.create_interaction_response(ctx, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d.content(announcement(participants.len())))
+        })
+        .await?;
+    }
+
+    reply
+        .edit(ctx, |m| {
+            m.content(format!(
+                "Signups for the nickname roulette are closed: {} joined.",
+                participants.len()
+            ))
+            .components(|c| c)
+        })
+        .await?;
+
+    if participants.is_empty() {
+        return Ok(());
+    }
+
+    let roulette = Roulette {
+        guild_id,
+        channel_id: ctx.channel_id(),
+        ends_at: unix_now() + i64::from(duration) * 60,
+    };
+    if !storage.start_roulette(&roulette)? {
+        ctx.say("A nickname roulette is already running here.")
+            .await?;
+        return Ok(());
+    }
+    // Scheduled before anything can fail, so nicknames changed before an
+    // error are still restored. The restore is skipped if the roulette
+    // has been ended by then.
+    let ends_at = roulette.ends_at;
+    schedule_restore(
+        ctx.serenity_context().http.clone(),
+        storage.clone(),
+        roulette.clone(),
+    );
+
+    let working_msg = ctx.say("Spinning the roulette...").await?;
+
+    // Participants get the server's defaults, not the organizer's.
+    let settings = guild_settings(ctx);
+    let defaults = settings.defaults.clone().or(config().defaults.clone());
+
+    let mut results = vec![];
+    let mut ended = false;
+    for user_id in participants {
+        let member = match guild_id.member(ctx, user_id).await {
+            Ok(member) => member,
+            Err(e) => {
+                results.push(format!("<@{}>: skipped ({})", user_id, e));
+                continue;
+            }
+        };
+        if let Err(e) = check_renamable(ctx, &member, false).await {
+            results.push(format!("{}: skipped ({})", member.user.name, e));
+            continue;
+        }
+
+        let mut name_options = NameOptions {
+            mode,
+            given_usage: theme.clone(),
+            surname_usage: theme.clone(),
+            exclusions: settings.exclusions.clone(),
+            seed: random_seed(),
+            ..Default::default()
+        };
+        name_options.fill_defaults(&defaults);
+        let name = match tokio::task::spawn_blocking(move || _name(name_options)).await? {
+            Ok(name) => name,
+            Err(e) => {
+                results.push(format!("{}: skipped ({})", member.user.name, e));
+                continue;
+            }
+        };
+
+        // Remember the original before changing it, so a restart in
+        // between can't lose it. /nickname_roulette end may run during
+        // the spin; nobody is renamed after it.
+        if !storage.add_roulette_nickname(&roulette, user_id, member.nick.as_deref())? {
+            ended = true;
+            break;
+        }
+
+        let nickname = nickname(&name, FormatOptions::default());
+        match member.edit(ctx, |m| m.nickname(&nickname)).await {
+            Ok(_) => results.push(format!("{} is now {}", member.user.name, nickname)),
+            Err(e) => results.push(format!("{}: skipped ({})", member.user.name, e)),
+        }
+
+        // If it ended while this member was being renamed, their
+        // original nickname was restored before the rename; restore it
+        // again.
+        if !storage.roulette_running(&roulette)? {
+            let original = member.nick.clone().unwrap_or_default();
+            if let Err(e) = member.edit(ctx, |m| m.nickname(original)).await {
+                error!("At restoring nickname of {}: {}", user_id, e);
+            }
+            ended = true;
+            break;
+        }
+    }
+
+    if ended {
+        working_msg
+            .edit(ctx, |m| {
+                m.content("The nickname roulette was ended while it was spinning.")
+            })
+            .await?;
+        return Ok(());
+    }
+
+    working_msg
+        .edit(ctx, |m| {
+            m.content(format!(
+                "The nickname roulette ends <t:{}:R>.\n{}",
+                ends_at,
+                results.join("\n")
+            ))
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// End this server's nickname roulette now.
+#[poise::command(slash_command, prefix_command, guild_only, rename = "end")]
+pub(crate) async fn roulette_end(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let storage = &ctx.data().storage;
+
+    if storage.roulette(guild_id)?.is_none() {
+        ctx.say("No nickname roulette is running here.").await?;
+        return Ok(());
+    }
+
+    let restored = restore_roulette(&ctx.serenity_context().http, storage, guild_id).await?;
+    ctx.say(format!(
+        "The nickname roulette is over! Restored {} nicknames.",
+        restored
+    ))
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,312 @@
+use behindthename::types::Gender;
+use poise::serenity_prelude::{GuildChannel, GuildId};
+use tracing::warn;
+
+use crate::config::config;
+use crate::resources::types::*;
+use crate::storage::settings::GuildSettings;
+use crate::storage::Storage;
+
+pub(crate) const MAX_PREFIX_LENGTH: usize = 5;
+
+/// The settings of the guild a command was used in, or the defaults in
+/// DMs. Settings that can't be read aren't worth failing the command over,
+/// so errors are only logged.
+pub(crate) fn guild_settings(ctx: Context<'_>) -> GuildSettings {
+    stored_settings(&ctx.data().storage, ctx.guild_id())
+}
+
+/// `guild_settings` for a guild, or for DMs if there's none.
+pub(crate) fn stored_settings(storage: &Storage, guild_id: Option<GuildId>) -> GuildSettings {
+    let Some(guild_id) = guild_id else {
+        return GuildSettings::default();
+    };
+    storage.guild_settings(guild_id).unwrap_or_else(|e| {
+        warn!("At loading settings of {}: {}", guild_id, e);
+        GuildSettings::default()
+    })
+}
+
+/// The prefix for prefix commands in the guild a message was sent in.
+pub(crate) async fn guild_prefix(
+    ctx: poise::PartialContext<'_, Data, Error>,
+) -> Result<Option<String>, Error> {
+    let prefix = match ctx.guild_id {
+        Some(guild_id) => ctx.data.storage.guild_settings(guild_id)?.prefix,
+        None => None,
+    };
+    Ok(Some(prefix.unwrap_or_else(|| config().prefix.clone())))
+}
+
+/// Whether a prefix is short enough to type and has no spaces.
+pub(crate) fn valid_prefix(prefix: &str) -> bool {
+    let length = prefix.chars().count();
+    (1..=MAX_PREFIX_LENGTH).contains(&length) && !prefix.contains(char::is_whitespace)
+}
+
+/// Answer application commands ephemerally where the guild asks for it.
+pub(crate) fn apply_ephemeral(ctx: Context<'_>, reply: &mut poise::CreateReply<'_>) {
+    if guild_settings(ctx).ephemeral {
+        reply.ephemeral = true;
+    }
+}
+
+/// Only run commands in the guild's allowed channels. Members who can
+/// manage the server may use commands anywhere, so they can't lock
+/// themselves out.
+pub(crate) async fn check_channel(ctx: Context<'_>) -> Result<bool, Error> {
+    let channels = guild_settings(ctx).channels;
+    if channels.is_empty() || channels.contains(&ctx.channel_id()) {
+        return Ok(true);
+    }
+
+    let can_manage = match ctx.author_member().await {
+        Some(member) => member.permissions(ctx).is_ok_and(|p| p.manage_guild()),
+        None => false,
+    };
+    if can_manage {
+        return Ok(true);
+    }
+
+    // Prefix commands are ignored quietly, but slash commands need an
+    // answer.
+    if let Context::Application(_) = ctx {
+        ctx.send(|m| {
+            m.content(format!(
+                "Commands can only be used in {} here.",
+                mention_channels(&channels)
+            ))
+            .ephemeral(true)
+        })
+        .await?;
+    }
+    Ok(false)
+}
+
+fn mention_channels(channels: &[poise::serenity_prelude::ChannelId]) -> String {
+    channels
+        .iter()
+        .map(|channel| format!("<#{}>", channel))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn describe(settings: &GuildSettings) -> String {
+    let or_default = |value: Option<String>, default: &str| value.unwrap_or_else(|| default.into());
+    let defaults = &settings.defaults;
+    [
+        format!(
+            "Gender: {}",
+            or_default(defaults.gender.map(|gender| gender.to_string()), "any")
+        ),
+        format!(
+            "Mode: {}",
+            or_default(defaults.mode.map(|mode| mode.to_string()), "coherent")
+        ),
+        format!(
+            "Usages: {}",
+            or_default(
+                defaults.usages.as_ref().map(|usages| usages.to_string()),
+                "any"
+            )
+        ),
+        format!(
+            "Excluded: {}",
+            Some(settings.exclusions.to_string())
+                .filter(|exclusions| !exclusions.is_empty())
+                .unwrap_or_else(|| "nothing".into())
+        ),
+        format!(
+            "Ephemeral responses: {}",
+            if settings.ephemeral { "on" } else { "off" }
+        ),
+        format!(
+            "Prefix: {}",
+            settings.prefix.as_ref().unwrap_or(&config().prefix)
+        ),
+        format!(
+            "Channels: {}",
+            if settings.channels.is_empty() {
+                "all".into()
+            } else {
+                mention_channels(&settings.channels)
+            }
+        ),
+    ]
+    .join("\n")
+}
+
+/// Change this server's settings, then show them.
+async fn update(ctx: Context<'_>, update: impl FnOnce(&mut GuildSettings)) -> Result<(), Error> {
+    let settings = ctx
+        .data()
+        .storage
+        .update_guild_settings(ctx.guild_id().unwrap(), update)?;
+
+    ctx.say(describe(&settings)).await?;
+    Ok(())
+}
+
+/// Manage this server's defaults and restrictions.
+///
+/// Manage this server's defaults and restrictions.
+///
+/// gender, mode and usages set the defaults for /name, /rename, \
+/// the nickname roulette and the name of the day; options passed \
+/// to a command and members' own defaults (see /rnc_prefs) take \
+/// precedence. Default usages (e.g. iri sco) \
+/// are used when neither usages nor a mode are passed. /family \
+/// and /another_life don't use them: a family's genders are given \
+/// per member and its culture follows its head, and another life \
+/// is fixed for each user. Excluded usages are managed with \
+/// /exclusions.
+///
+/// ephemeral makes slash command responses visible only to whoever \
+/// used the command. prefix changes the prefix for prefix commands \
+/// (~ unless the bot is configured otherwise). channel allows or disallows a channel; once any \
+/// channel is allowed, commands only work in allowed channels, \
+/// except for members who can manage the server.
+///
+/// Leave the value out to go back to the default; reset clears \
+/// every setting.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands(
+        "settings_show",
+        "settings_gender",
+        "settings_mode",
+        "settings_usages",
+        "settings_ephemeral",
+        "settings_prefix",
+        "settings_channel",
+        "settings_reset"
+    )
+)]
+pub(crate) async fn rnc_settings(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show this server's settings.
+#[poise::command(slash_command, prefix_command, guild_only, ephemeral, rename = "show")]
+pub(crate) async fn settings_show(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.say(describe(&guild_settings(ctx))).await?;
+    Ok(())
+}
+
+/// Set the default gender of names.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    ephemeral,
+    rename = "gender"
+)]
+pub(crate) async fn settings_gender(
+    ctx: Context<'_>,
+    #[description = "Default gender, optional: m|f|u (default any)"] gender: Option<Gender>,
+) -> Result<(), Error> {
+    update(ctx, |settings| settings.defaults.gender = gender).await
+}
+
+/// Set the default generation mode.
+#[poise::command(slash_command, prefix_command, guild_only, ephemeral, rename = "mode")]
+pub(crate) async fn settings_mode(
+    ctx: Context<'_>,
+    #[description = "Default mode, optional (default coherent)"] mode: Option<GenMode>,
+) -> Result<(), Error> {
+    update(ctx, |settings| settings.defaults.mode = mode).await
+}
+
+/// Set the default usages of names.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    ephemeral,
+    rename = "usages"
+)]
+pub(crate) async fn settings_usages(
+    ctx: Context<'_>,
+    #[description = "Default usages, optional: e.g. iri sco or jap:3 kor (default any)"]
+    #[rest]
+    usages: Option<UsageSpec>,
+) -> Result<(), Error> {
+    update(ctx, |settings| settings.defaults.usages = usages).await
+}
+
+/// Make slash command responses visible only to whoever used them.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    ephemeral,
+    rename = "ephemeral"
+)]
+pub(crate) async fn settings_ephemeral(
+    ctx: Context<'_>,
+    #[description = "Whether responses are ephemeral"] enabled: bool,
+) -> Result<(), Error> {
+    update(ctx, |settings| settings.ephemeral = enabled).await
+}
+
+/// Set the prefix for prefix commands.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    ephemeral,
+    rename = "prefix"
+)]
+pub(crate) async fn settings_prefix(
+    ctx: Context<'_>,
+    #[description = "Prefix, optional (default the bot's)"] prefix: Option<String>,
+) -> Result<(), Error> {
+    if let Some(prefix) = &prefix {
+        if !valid_prefix(prefix) {
+            ctx.say(format!(
+                "Prefixes must be up to {} characters long, without spaces.",
+                MAX_PREFIX_LENGTH
+            ))
+            .await?;
+            return Ok(());
+        }
+    }
+
+    update(ctx, |settings| settings.prefix = prefix).await
+}
+
+/// Allow or disallow commands in a channel.
+#[poise::command(
+    slash_command,
+    prefix_command,
+    guild_only,
+    ephemeral,
+    rename = "channel"
+)]
+pub(crate) async fn settings_channel(
+    ctx: Context<'_>,
+    #[description = "Channel"] channel: GuildChannel,
+    #[description = "Whether commands are allowed there"] allowed: bool,
+) -> Result<(), Error> {
+    update(ctx, |settings| {
+        settings.channels.retain(|id| *id != channel.id);
+        if allowed {
+            settings.channels.push(channel.id);
+        }
+    })
+    .await
+}
+
+/// Reset every setting of this server, including exclusions.
+#[poise::command(slash_command, prefix_command, guild_only, ephemeral, rename = "reset")]
+pub(crate) async fn settings_reset(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.data()
+        .storage
+        .remove_guild_settings(ctx.guild_id().unwrap())?;
+
+    ctx.say(describe(&GuildSettings::default())).await?;
+    Ok(())
+}
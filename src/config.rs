@@ -0,0 +1,381 @@
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::io::ErrorKind;
+use std::mem;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use ed25519_dalek::VerifyingKey;
+use poise::serenity_prelude::GuildId;
+use serde::Deserialize;
+use tracing::Level;
+
+use crate::commands::settings::{valid_prefix, MAX_PREFIX_LENGTH};
+use crate::interactions::parse_public_key;
+use crate::resources::maps::UsageRules;
+use crate::resources::types::NameDefaults;
+
+const DEFAULT_CONFIG_PATH: &str = "randomnamecord.toml";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// How the bot receives commands from Discord.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Mode {
+    /// Over a gateway connection, with every command.
+    #[default]
+    Gateway,
+    /// As HTTP requests to the interactions endpoint, with the commands
+    /// that don't need the gateway.
+    Interactions,
+}
+
+impl FromStr for Mode {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.into()))
+    }
+}
+
+/// Bot-wide settings, read once at startup.
+pub(crate) struct Config {
+    pub(crate) mode: Mode,
+    /// Empty unless it was required.
+    pub(crate) discord_token: String,
+    /// The application's public key, which signs interactions; only in
+    /// interactions mode.
+    pub(crate) discord_public_key: Option<VerifyingKey>,
+    /// Empty unless it was required or given.
+    pub(crate) btn_api_key: String,
+    /// The prefix for prefix commands in guilds that haven't set their own.
+    pub(crate) prefix: String,
+    pub(crate) database_path: String,
+    /// How long to wait before each BehindTheName request.
+    pub(crate) request_interval: Duration,
+    /// How many messages serenity keeps per channel.
+    pub(crate) max_cached_messages: usize,
+    /// How many BehindTheName page lookups to remember.
+    pub(crate) max_cached_pages: usize,
+    /// Where to serve the HTTP API, if anywhere.
+    pub(crate) http_listen: Option<SocketAddr>,
+    /// The defaults for names, beneath guild and user defaults.
+    pub(crate) defaults: NameDefaults,
+    /// Register commands in this guild only, which takes effect at once,
+    /// instead of globally.
+    pub(crate) test_guild: Option<GuildId>,
+    pub(crate) log_level: Level,
+    /// The usage rewrite rules, bundled or from `usage_rules`, until
+    /// they're installed.
+    usage_rules: UsageRules,
+}
+
+/// The config file as written, before validation.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    mode: Mode,
+    discord_token: Option<String>,
+    discord_public_key: Option<String>,
+    btn_api_key: Option<String>,
+    prefix: String,
+    database_path: String,
+    test_guild: Option<u64>,
+    /// A JSON file of usage rewrite rules replacing the bundled ones.
+    usage_rules: Option<String>,
+    btn: BtnSection,
+    cache: CacheSection,
+    http: HttpSection,
+    defaults: DefaultsSection,
+    logging: LoggingSection,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BtnSection {
+    request_interval_ms: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CacheSection {
+    max_messages: usize,
+    max_pages: usize,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct HttpSection {
+    listen: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DefaultsSection {
+    gender: Option<String>,
+    mode: Option<String>,
+    usages: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LoggingSection {
+    level: String,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        ConfigFile {
+            mode: Mode::default(),
+            discord_token: None,
+            discord_public_key: None,
+            btn_api_key: None,
+            prefix: "~".into(),
+            database_path: "randomnamecord.db".into(),
+            test_guild: None,
+            usage_rules: None,
+            btn: BtnSection::default(),
+            cache: CacheSection::default(),
+            http: HttpSection::default(),
+            defaults: DefaultsSection::default(),
+            logging: LoggingSection::default(),
+        }
+    }
+}
+
+impl Default for BtnSection {
+    fn default() -> Self {
+        BtnSection {
+            request_interval_ms: 550,
+        }
+    }
+}
+
+impl Default for CacheSection {
+    fn default() -> Self {
+        CacheSection {
+            max_messages: 0,
+            max_pages: 1000,
+        }
+    }
+}
+
+impl Default for LoggingSection {
+    fn default() -> Self {
+        LoggingSection {
+            level: "info".into(),
+        }
+    }
+}
+
+/// An environment variable parsed as `T`, if it's set.
+fn env_var<T: FromStr>(name: &str, errors: &mut Vec<String>) -> Option<T>
+where
+    T::Err: Display,
+{
+    let value = env::var(name).ok()?;
+    value
+        .parse()
+        .map_err(|e| errors.push(format!("{}: {}", name, e)))
+        .ok()
+}
+
+/// A setting parsed as `T`, if it's set.
+fn parse<T: FromStr>(name: &str, value: Option<&str>, errors: &mut Vec<String>) -> Option<T>
+where
+    T::Err: Display,
+{
+    value?
+        .parse()
+        .map_err(|e| errors.push(format!("{}: {}", name, e)))
+        .ok()
+}
+
+/// Load `.env`, if there is one, into the environment.
+fn load_dotenv() -> Result<(), String> {
+    match dotenv::dotenv() {
+        Ok(_) => Ok(()),
+        Err(dotenv::Error::Io(e)) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("At reading .env: {}", e)),
+    }
+}
+
+/// Read the config file, if there is one. The file is only required when
+/// its path is given with `RNC_CONFIG`.
+fn read_file() -> Result<ConfigFile, String> {
+    let (path, required) = match env::var("RNC_CONFIG") {
+        Ok(path) => (path, true),
+        Err(_) => (DEFAULT_CONFIG_PATH.into(), false),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound && !required => return Ok(ConfigFile::default()),
+        Err(e) => return Err(format!("At reading {}: {}", path, e)),
+    };
+    toml::from_str(&text).map_err(|e| format!("At parsing {}: {}", path, e))
+}
+
+impl Config {
+    /// Read the config file and `.env`, with environment variables taking
+    /// precedence, and check the result. Every problem found is reported at
+    /// once. The Discord token is only required for the bot, and the
+    /// BehindTheName API key for all but the REPL, which can name offline.
+    pub(crate) fn load(
+        needs_discord_token: bool,
+        needs_btn_api_key: bool,
+    ) -> Result<Config, String> {
+        load_dotenv()?;
+        let mut file = read_file()?;
+
+        let mut errors = Vec::new();
+        if let Some(mode) = env_var("RNC_MODE", &mut errors) {
+            file.mode = mode;
+        }
+        file.discord_token = env_var("DISCORD_TOKEN", &mut errors).or(file.discord_token);
+        file.discord_public_key =
+            env_var("DISCORD_PUBLIC_KEY", &mut errors).or(file.discord_public_key);
+        file.btn_api_key = env_var("BTN_API_KEY", &mut errors).or(file.btn_api_key);
+        if let Some(prefix) = env_var("RNC_PREFIX", &mut errors) {
+            file.prefix = prefix;
+        }
+        if let Some(path) = env_var("DATABASE_PATH", &mut errors) {
+            file.database_path = path;
+        }
+        file.test_guild = env_var("TEST_GUILD_ID", &mut errors).or(file.test_guild);
+        if let Some(interval) = env_var("BTN_REQUEST_INTERVAL_MS", &mut errors) {
+            file.btn.request_interval_ms = interval;
+        }
+        if let Some(max_messages) = env_var("MAX_CACHED_MESSAGES", &mut errors) {
+            file.cache.max_messages = max_messages;
+        }
+        if let Some(max_pages) = env_var("MAX_CACHED_PAGES", &mut errors) {
+            file.cache.max_pages = max_pages;
+        }
+        file.http.listen = env_var("HTTP_LISTEN", &mut errors).or(file.http.listen);
+        file.usage_rules = env_var("USAGE_RULES_PATH", &mut errors).or(file.usage_rules);
+        if let Some(level) = env_var("LOG_LEVEL", &mut errors) {
+            file.logging.level = level;
+        }
+
+        let discord_token = file.discord_token.filter(|token| !token.trim().is_empty());
+        if needs_discord_token && discord_token.is_none() {
+            errors
+                .push("discord_token: missing; set it in the config file or DISCORD_TOKEN".into());
+        }
+        let btn_api_key = file.btn_api_key.filter(|key| !key.trim().is_empty());
+        if needs_btn_api_key && btn_api_key.is_none() {
+            errors.push("btn_api_key: missing; set it in the config file or BTN_API_KEY".into());
+        }
+        if !valid_prefix(&file.prefix) {
+            errors.push(format!(
+                "prefix: {:?} isn't 1 to {} characters without spaces",
+                file.prefix, MAX_PREFIX_LENGTH
+            ));
+        }
+        if file.database_path.trim().is_empty() {
+            errors.push("database_path: empty".into());
+        }
+        if file.test_guild == Some(0) {
+            errors.push("test_guild: not a guild ID".into());
+        }
+
+        let defaults = NameDefaults {
+            gender: parse(
+                "defaults.gender",
+                file.defaults.gender.as_deref(),
+                &mut errors,
+            ),
+            mode: parse("defaults.mode", file.defaults.mode.as_deref(), &mut errors),
+            usages: parse(
+                "defaults.usages",
+                file.defaults.usages.as_deref(),
+                &mut errors,
+            ),
+        };
+        let log_level = parse("logging.level", Some(&file.logging.level), &mut errors);
+        let http_listen = parse("http.listen", file.http.listen.as_deref(), &mut errors);
+        let mut discord_public_key = None;
+        if needs_discord_token && file.mode == Mode::Interactions {
+            match file.discord_public_key.as_deref().map(parse_public_key) {
+                Some(Ok(key)) => discord_public_key = Some(key),
+                Some(Err(e)) => errors.push(format!("discord_public_key: {}", e)),
+                None => errors.push(
+                    "discord_public_key: missing; interactions mode needs it to check requests"
+                        .into(),
+                ),
+            }
+            if file.http.listen.is_none() {
+                errors.push(
+                    "http.listen: missing; interactions mode serves the endpoint there".into(),
+                );
+            }
+        }
+        let usage_rules = match &file.usage_rules {
+            Some(path) => UsageRules::read(path)
+                .map_err(|e| errors.push(format!("usage_rules: {}", e)))
+                .ok(),
+            None => Some(UsageRules::bundled()),
+        };
+
+        if !errors.is_empty() {
+            return Err(errors.join("\n"));
+        }
+        Ok(Config {
+            mode: file.mode,
+            discord_token: discord_token.unwrap_or_default(),
+            discord_public_key,
+            btn_api_key: btn_api_key.unwrap_or_default(),
+            prefix: file.prefix,
+            database_path: file.database_path,
+            request_interval: Duration::from_millis(file.btn.request_interval_ms),
+            max_cached_messages: file.cache.max_messages,
+            max_cached_pages: file.cache.max_pages,
+            http_listen,
+            defaults,
+            test_guild: file.test_guild.map(GuildId),
+            log_level: log_level.unwrap(),
+            usage_rules: usage_rules.unwrap(),
+        })
+    }
+
+    /// Make this the configuration returned by `config()`, and install its
+    /// usage rules.
+    pub(crate) fn install(mut self) -> &'static Config {
+        mem::take(&mut self.usage_rules).install();
+        if CONFIG.set(self).is_err() {
+            panic!("The configuration was already installed");
+        }
+        config()
+    }
+}
+
+/// The bot's configuration.
+pub(crate) fn config() -> &'static Config {
+    CONFIG.get().expect("The configuration isn't loaded yet")
+}
+
+/// Install a configuration without secrets or name defaults for tests, once
+/// for every test that needs `config()`.
+#[cfg(test)]
+pub(crate) fn install_test_config() -> &'static Config {
+    CONFIG.get_or_init(|| Config {
+        mode: Mode::default(),
+        discord_token: String::new(),
+        discord_public_key: None,
+        btn_api_key: String::new(),
+        prefix: "!".into(),
+        database_path: ":memory:".into(),
+        request_interval: Duration::ZERO,
+        max_cached_messages: 0,
+        max_cached_pages: 0,
+        http_listen: None,
+        defaults: NameDefaults::default(),
+        test_guild: None,
+        log_level: Level::INFO,
+        usage_rules: UsageRules::default(),
+    })
+}
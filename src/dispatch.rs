@@ -0,0 +1,167 @@
+//! Slash commands run without a poise context, for the interactions
+//! endpoint and the REPL. Only the commands that don't need the gateway are
+//! available.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use poise::serenity_prelude::{GuildId, UserId};
+use tracing::error;
+
+use crate::commands::names::{
+    _about, about_message, an_error_occurred, name_message, MessageContent, NameArgs, NameScope,
+};
+use crate::naming::format::nickname;
+use crate::resources::types::*;
+use crate::storage::settings::GuildSettings;
+use crate::storage::Storage;
+
+/// The commands that can be run this way.
+pub(crate) const COMMANDS: [&str; 2] = ["name", "about_name"];
+
+/// A command as a user invoked it, with its options as text.
+pub(crate) struct Invocation {
+    pub(crate) command: String,
+    pub(crate) options: Vec<(String, String)>,
+    pub(crate) user_id: UserId,
+    pub(crate) guild_id: Option<GuildId>,
+    /// The user's nickname in the guild, looked up by /about_name.
+    pub(crate) nickname: Option<String>,
+    /// Whether the command is only simulated, as in the REPL, so that
+    /// /name's apply can answer with the nickname instead of setting it.
+    pub(crate) simulated: bool,
+}
+
+/// What a command answers with.
+pub(crate) struct Answer {
+    pub(crate) message: MessageContent,
+    /// The nickname /name's apply would set, in a simulation.
+    pub(crate) nickname: Option<String>,
+}
+
+impl Answer {
+    fn text(content: impl Into<String>) -> Answer {
+        Answer {
+            message: MessageContent {
+                content: content.into(),
+                embed: None,
+            },
+            nickname: None,
+        }
+    }
+}
+
+/// An option that doesn't parse, which is the user's mistake rather than
+/// an error to log.
+#[derive(Debug)]
+struct InvalidOption(String);
+
+impl Display for InvalidOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid option {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidOption {}
+
+impl Invocation {
+    /// An option passed to the command, parsed as `T`.
+    fn option<T: FromStr>(&self, name: &str) -> Result<Option<T>, InvalidOption>
+    where
+        T::Err: Display,
+    {
+        self.options
+            .iter()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| value.parse())
+            .transpose()
+            .map_err(|e| InvalidOption(format!("{}: {}", name, e)))
+    }
+}
+
+/// Run a command, returning what to answer with. Errors are logged and
+/// answered as in Discord.
+pub(crate) async fn run(
+    storage: &Storage,
+    invocation: &Invocation,
+    settings: GuildSettings,
+) -> Answer {
+    let result = match invocation.command.as_str() {
+        "name" => name(storage, invocation, settings).await,
+        "about_name" => about_name(invocation).await,
+        other => Ok(Answer::text(format!(
+            "/{} needs the bot's gateway deployment.",
+            other
+        ))),
+    };
+    result.unwrap_or_else(|e| {
+        if e.is::<InvalidOption>() {
+            Answer::text(e.to_string())
+        } else {
+            error!("At running /{}: {}", invocation.command, e);
+            Answer::text(an_error_occurred(e.to_string()))
+        }
+    })
+}
+
+/// /name, without avoid_roster, and with apply only in a simulation, on the
+/// slash command's own body.
+async fn name(
+    storage: &Storage,
+    invocation: &Invocation,
+    settings: GuildSettings,
+) -> Result<Answer, Error> {
+    if invocation.option::<String>("avoid_roster")?.is_some() {
+        return Ok(Answer::text(
+            "avoid_roster needs the bot's gateway deployment.",
+        ));
+    }
+    let apply = invocation.option::<bool>("apply")?.unwrap_or(false);
+    if apply && !invocation.simulated {
+        return Ok(Answer::text("apply needs the bot's gateway deployment."));
+    }
+    if apply && invocation.guild_id.is_none() {
+        return Ok(Answer::text("Nicknames can only be applied in a server."));
+    }
+
+    let scope = NameScope::new(storage, invocation.user_id, invocation.guild_id, settings);
+    let name_options = scope.name_options(NameArgs {
+        gender: invocation.option("gender")?,
+        mode: invocation.option("mode")?,
+        given_usage: invocation.option("given_usage")?,
+        surname_usage: invocation.option("surname_usage")?,
+        heritage: invocation.option("heritage")?,
+        exclude: invocation.option("exclude")?,
+        constraints: invocation.option("constraints")?,
+        seed: invocation.option("seed")?,
+        backend: invocation.option("backend")?,
+    })?;
+    let format_options = scope.format_options(
+        invocation.option("western_order")?,
+        invocation.option("surname_conjunction")?,
+    );
+
+    let seed = name_options.seed;
+    let name = scope.generate(name_options).await?;
+    Ok(Answer {
+        message: name_message(&name, format_options, Some(seed)),
+        nickname: apply.then(|| nickname(&name, format_options)),
+    })
+}
+
+/// /about_name, looking up the user's nickname when no name is given.
+async fn about_name(invocation: &Invocation) -> Result<Answer, Error> {
+    let name = invocation
+        .option::<String>("name")?
+        .or_else(|| invocation.nickname.clone());
+    let names = name
+        .iter()
+        .flat_map(|name| name.split_whitespace())
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let parts = _about(&names).await?;
+    Ok(Answer {
+        message: about_message(&names, parts),
+        nickname: None,
+    })
+}
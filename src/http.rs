@@ -0,0 +1,488 @@
+//! The HTTP API: the name generator as JSON, for tools other than Discord.
+
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::rejection::QueryRejection;
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use behindthename::types::{Gender, Usage};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::timeout;
+use tracing::info;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use crate::btn::random_seed;
+use crate::commands::names::{_about, _dbg_name, _name};
+use crate::config::config;
+use crate::interactions::{self, Interactions};
+use crate::naming::export::{NameRecord, PartRecord};
+use crate::naming::format::{FormatOptions, PartKind};
+use crate::resources::types::*;
+
+/// How many requests are answered at once. BehindTheName requests are
+/// spaced out for every session together, so more would only queue up
+/// blocking threads waiting their turn.
+const MAX_CONCURRENT_REQUESTS: usize = 4;
+/// How long a request waits for its turn before it's refused.
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a request may take once its turn has come. Generation keeps
+/// its turn until it ends, even once the request has timed out.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+lazy_static! {
+    static ref TURNS: Arc<Semaphore> = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "randomnamecord",
+        description = "Random names from BehindTheName."
+    ),
+    paths(name, surname_for, about),
+    components(schemas(ApiName, ApiAbout, ApiUsage, ApiPart, ApiError))
+)]
+struct ApiDoc;
+
+/// A usage of a name on BehindTheName, e.g. "iri", "Irish".
+#[derive(Serialize, ToSchema)]
+struct ApiUsage {
+    code: String,
+    name: String,
+}
+
+/// A part of a name, linked to its BehindTheName page where it has one.
+#[derive(Serialize, ToSchema)]
+struct ApiPart {
+    /// given, patronymic, family or epithet
+    #[schema(value_type = String)]
+    kind: PartKind,
+    /// e.g. "First Name" or "Last Name"
+    label: String,
+    text: String,
+    url: Option<String>,
+}
+
+/// A generated name.
+#[derive(Serialize, ToSchema)]
+struct ApiName {
+    /// The name in the order conventional for its culture, unless western
+    /// order was asked for.
+    full_name: String,
+    /// The generation mode, or "mixed" when usages were given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mode: Option<String>,
+    /// The seed to pass to generate the name's local choices again.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    /// The usage of the name, or of the surname for mixed names.
+    usage: Option<ApiUsage>,
+    /// The usage of the given name, for mixed names.
+    given_usage: Option<ApiUsage>,
+    parts: Vec<ApiPart>,
+}
+
+/// A name looked up on BehindTheName.
+#[derive(Serialize, ToSchema)]
+struct ApiAbout {
+    full_name: String,
+    /// The parts found on BehindTheName.
+    parts: Vec<ApiPart>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ApiError {
+    error: String,
+    #[serde(skip)]
+    status: StatusCode,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+fn bad_request(error: impl Display) -> ApiError {
+    ApiError {
+        error: error.to_string(),
+        status: StatusCode::BAD_REQUEST,
+    }
+}
+
+/// BehindTheName failed, or had no name to give.
+fn bad_gateway(error: impl Display) -> ApiError {
+    ApiError {
+        error: error.to_string(),
+        status: StatusCode::BAD_GATEWAY,
+    }
+}
+
+/// Wait for a turn to answer a request, for as long as it takes.
+pub(crate) async fn wait_for_turn() -> OwnedSemaphorePermit {
+    TURNS.clone().acquire_owned().await.unwrap()
+}
+
+/// Wait for a turn to answer a request, within `QUEUE_TIMEOUT`.
+async fn turn() -> Result<OwnedSemaphorePermit, ApiError> {
+    match timeout(QUEUE_TIMEOUT, wait_for_turn()).await {
+        Ok(permit) => Ok(permit),
+        Err(_) => Err(ApiError {
+            error: "Too many requests at once, try again later".into(),
+            status: StatusCode::SERVICE_UNAVAILABLE,
+        }),
+    }
+}
+
+/// Run a blocking generation in its turn, within `REQUEST_TIMEOUT`.
+async fn generate<T: Send + 'static>(
+    generation: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, ApiError> {
+    let permit = turn().await?;
+    let task = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        generation()
+    });
+    match timeout(REQUEST_TIMEOUT, task).await {
+        Ok(result) => result.map_err(bad_gateway)?.map_err(bad_gateway),
+        Err(_) => Err(gateway_timeout()),
+    }
+}
+
+fn gateway_timeout() -> ApiError {
+    ApiError {
+        error: "BehindTheName took too long to answer".into(),
+        status: StatusCode::GATEWAY_TIMEOUT,
+    }
+}
+
+/// The query parameters, or a bad request in the API's error shape when
+/// they're missing or not of their type.
+fn query<T>(query: Result<Query<T>, QueryRejection>) -> Result<T, ApiError> {
+    query
+        .map(|Query(query)| query)
+        .map_err(|e| bad_request(e.body_text()))
+}
+
+/// Parse a query parameter, if it's given.
+fn parse<T: FromStr>(name: &str, value: Option<String>) -> Result<Option<T>, ApiError>
+where
+    T::Err: Display,
+{
+    value
+        .map(|value| value.parse())
+        .transpose()
+        .map_err(|e| bad_request(format!("{}: {}", name, e)))
+}
+
+impl From<&Usage> for ApiUsage {
+    fn from(usage: &Usage) -> Self {
+        ApiUsage {
+            code: usage.usage_code.clone(),
+            name: usage.usage_full.clone(),
+        }
+    }
+}
+
+impl From<PartRecord> for ApiPart {
+    fn from(part: PartRecord) -> Self {
+        ApiPart {
+            kind: part.kind,
+            label: part.label,
+            text: part.text,
+            url: part.url,
+        }
+    }
+}
+
+impl ApiName {
+    fn new(name: &Name, options: FormatOptions, mode: Option<String>, seed: Option<u64>) -> Self {
+        let record = NameRecord::new(name, options, None);
+        ApiName {
+            full_name: record.full_name,
+            mode,
+            seed,
+            usage: name.usage.as_ref().map(ApiUsage::from),
+            given_usage: name.given_usage.as_ref().map(ApiUsage::from),
+            parts: record.parts.into_iter().map(ApiPart::from).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct NameQuery {
+    /// m, f or u; any gender if left out
+    gender: Option<String>,
+    /// coherent, chaotic, medieval, mythology, biblical or ancient
+    mode: Option<String>,
+    /// Usages of the given name, e.g. "iri:3 ita"
+    given_usage: Option<String>,
+    /// Usages of the surname, e.g. "ita"
+    surname_usage: Option<String>,
+    /// Weighted usages for both names, e.g. "iri:3 ita:1"
+    heritage: Option<String>,
+    /// Usages or groups to exclude, e.g. "mythology bibl"
+    exclude: Option<String>,
+    /// Constraints, e.g. "starts:K length:-6 alliterative"
+    constraints: Option<String>,
+    /// Seed for the generator's local random choices
+    seed: Option<u64>,
+    /// btn for BehindTheName, or local for the bot's own name lists, which
+    /// always give the same name for a seed
+    backend: Option<String>,
+    /// Always put the given name first
+    western_order: Option<bool>,
+    /// Join double surnames with y/i/e
+    surname_conjunction: Option<bool>,
+}
+
+/// Generate a random name, as /name does.
+#[utoipa::path(
+    get,
+    path = "/v1/name",
+    tag = "names",
+    params(NameQuery),
+    responses(
+        (status = 200, description = "A generated name", body = ApiName),
+        (status = 400, description = "An invalid parameter", body = ApiError),
+        (status = 502, description = "BehindTheName failed", body = ApiError),
+        (status = 503, description = "Too many requests at once", body = ApiError),
+        (status = 504, description = "BehindTheName took too long", body = ApiError)
+    )
+)]
+async fn name(query: Result<Query<NameQuery>, QueryRejection>) -> Result<Json<ApiName>, ApiError> {
+    let query = self::query(query)?;
+    let heritage: Option<UsageSpec> = parse("heritage", query.heritage)?;
+    let mut options = NameOptions {
+        gender: parse("gender", query.gender)?,
+        mode: parse("mode", query.mode)?,
+        given_usage: parse("given_usage", query.given_usage)?.or_else(|| heritage.clone()),
+        surname_usage: parse("surname_usage", query.surname_usage)?.or(heritage),
+        exclusions: parse("exclude", query.exclude)?.unwrap_or_default(),
+        constraints: parse("constraints", query.constraints)?.unwrap_or_default(),
+        seed: query.seed.unwrap_or_else(random_seed),
+        backend: parse("backend", query.backend)?.unwrap_or_default(),
+    };
+    options.fill_defaults(&config().defaults);
+    let format_options = FormatOptions {
+        western_order: query.western_order.unwrap_or(false),
+        surname_conjunction: query.surname_conjunction.unwrap_or(false),
+    };
+
+    let mode = options.mode_label();
+    let seed = options.seed;
+    let name = generate(move || _name(options)).await?;
+
+    Ok(Json(ApiName::new(
+        &name,
+        format_options,
+        Some(mode),
+        Some(seed),
+    )))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SurnameQuery {
+    /// The first name to find a surname for
+    first_name: String,
+    /// m, f or u; any gender if left out
+    gender: Option<String>,
+}
+
+/// Find a surname for a first name, in one of the first name's usages.
+#[utoipa::path(
+    get,
+    path = "/v1/surname-for",
+    tag = "names",
+    params(SurnameQuery),
+    responses(
+        (status = 200, description = "The first name with a surname", body = ApiName),
+        (status = 400, description = "An invalid parameter", body = ApiError),
+        (status = 502, description = "BehindTheName failed", body = ApiError),
+        (status = 503, description = "Too many requests at once", body = ApiError),
+        (status = 504, description = "BehindTheName took too long", body = ApiError)
+    )
+)]
+async fn surname_for(
+    query: Result<Query<SurnameQuery>, QueryRejection>,
+) -> Result<Json<ApiName>, ApiError> {
+    let query = self::query(query)?;
+    let gender: Option<Gender> = parse("gender", query.gender)?;
+    let first_name = query.first_name;
+    let name = generate(move || _dbg_name(first_name, gender)).await?;
+
+    Ok(Json(ApiName::new(
+        &name,
+        FormatOptions::default(),
+        None,
+        None,
+    )))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AboutQuery {
+    /// The name to look up, e.g. "Ada Lovelace"
+    name: String,
+}
+
+/// Look a name up on BehindTheName, as /about_name does.
+#[utoipa::path(
+    get,
+    path = "/v1/about",
+    tag = "names",
+    params(AboutQuery),
+    responses(
+        (status = 200, description = "The parts found", body = ApiAbout),
+        (status = 400, description = "An invalid parameter", body = ApiError),
+        (status = 404, description = "No part was found", body = ApiError),
+        (status = 502, description = "BehindTheName failed", body = ApiError),
+        (status = 503, description = "Too many requests at once", body = ApiError),
+        (status = 504, description = "BehindTheName took too long", body = ApiError)
+    )
+)]
+async fn about(
+    query: Result<Query<AboutQuery>, QueryRejection>,
+) -> Result<Json<ApiAbout>, ApiError> {
+    let query = self::query(query)?;
+    let names = query
+        .name
+        .split_whitespace()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let _turn = turn().await?;
+    let parts = timeout(REQUEST_TIMEOUT, _about(&names))
+        .await
+        .map_err(|_| gateway_timeout())?
+        .map_err(bad_gateway)?;
+    if parts.is_empty() {
+        return Err(ApiError {
+            error: format!("{} not found on BehindTheName", names.join(" ")),
+            status: StatusCode::NOT_FOUND,
+        });
+    }
+
+    Ok(Json(ApiAbout {
+        full_name: names.join(" "),
+        parts: parts.into_iter().map(ApiPart::from).collect(),
+    }))
+}
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+fn router(interactions: Option<Arc<Interactions>>) -> Router {
+    let router = Router::new()
+        .route("/v1/name", get(name))
+        .route("/v1/surname-for", get(surname_for))
+        .route("/v1/about", get(about))
+        .route("/v1/openapi.json", get(openapi));
+    match interactions {
+        Some(interactions) => router.merge(interactions::router(interactions)),
+        None => router,
+    }
+}
+
+/// Serve the HTTP API until the process ends, with the interactions
+/// endpoint if one is given. Its BehindTheName requests share the bot's
+/// rate limit and page cache.
+pub(crate) async fn serve(
+    address: SocketAddr,
+    interactions: Option<Arc<Interactions>>,
+) -> std::io::Result<()> {
+    let listener = TcpListener::bind(address).await?;
+    info!("Listening on {}", address);
+    axum::serve(listener, router(interactions)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn get(uri: &str) -> (StatusCode, Value) {
+        let response = router(None)
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn refuses_invalid_parameters() {
+        for (uri, parameter) in [
+            ("/v1/name?gender=x", "gender"),
+            ("/v1/name?mode=fancy", "mode"),
+            ("/v1/name?constraints=rhymes", "constraints"),
+            ("/v1/name?constraints=length:9-1", "constraints"),
+            ("/v1/name?heritage=iri:0", "heritage"),
+            ("/v1/name?backend=cloud", "backend"),
+            ("/v1/name?exclude=,", "exclude"),
+            ("/v1/surname-for?first_name=Ada&gender=x", "gender"),
+        ] {
+            let (status, body) = get(uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            let error = body["error"].as_str().unwrap();
+            assert!(
+                error.starts_with(&format!("{}: ", parameter)),
+                "{}: {}",
+                uri,
+                error
+            );
+            assert_eq!(body.as_object().unwrap().len(), 1, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_malformed_queries_in_the_error_shape() {
+        for uri in [
+            "/v1/name?seed=abc",
+            "/v1/name?western_order=maybe",
+            "/v1/surname-for",
+            "/v1/about",
+        ] {
+            let (status, body) = get(uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert!(body["error"].is_string(), "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_the_openapi_document() {
+        let (status, body) = get("/v1/openapi.json").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["openapi"].as_str().unwrap().starts_with('3'));
+        for path in ["/v1/name", "/v1/surname-for", "/v1/about"] {
+            assert!(body["paths"][path]["get"].is_object(), "{}", path);
+        }
+        let parameters = body["paths"]["/v1/name"]["get"]["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|parameter| parameter["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert!(parameters.contains(&"seed") && parameters.contains(&"backend"));
+        assert!(body["components"]["schemas"]["ApiError"].is_object());
+    }
+}
@@ -0,0 +1,336 @@
+//! The interactions endpoint: slash commands received as HTTP requests
+//! signed by Discord, for running the bot without a gateway connection.
+//!
+//! Discord expects an answer within three seconds, so commands are
+//! acknowledged with a deferred response at once, and the original response
+//! is edited once the name is ready.
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use ed25519_dalek::{Signature, VerifyingKey};
+use poise::serenity_prelude::{ApplicationCommandInteraction, Http, Interaction};
+use serde_json::{json, Value};
+use tracing::error;
+
+use crate::commands::names::MessageContent;
+use crate::commands::settings::stored_settings;
+use crate::dispatch::{self, Invocation};
+use crate::http;
+use crate::storage::Storage;
+
+/// Interaction response types, see
+/// https://discord.com/developers/docs/interactions/receiving-and-responding
+const PONG: u8 = 1;
+const CHANNEL_MESSAGE: u8 = 4;
+const DEFERRED_CHANNEL_MESSAGE: u8 = 5;
+/// The message flag for responses only the user sees.
+const EPHEMERAL: u64 = 1 << 6;
+
+/// What the endpoint needs to answer commands.
+pub(crate) struct Interactions {
+    pub(crate) http: Arc<Http>,
+    pub(crate) storage: Arc<Storage>,
+    pub(crate) public_key: VerifyingKey,
+}
+
+/// Parse an application's public key, as shown in the developer portal.
+pub(crate) fn parse_public_key(hex: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hex::decode(hex.trim())
+        .map_err(|e| format!("not hexadecimal: {}", e))?
+        .try_into()
+        .map_err(|_| "not 32 bytes long".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("not a valid key: {}", e))
+}
+
+/// Whether Discord signed `timestamp` followed by `body`, given the
+/// signature as hexadecimal.
+pub(crate) fn verify(
+    public_key: &VerifyingKey,
+    signature: &str,
+    timestamp: &str,
+    body: &[u8],
+) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let Ok(signature) = Signature::from_slice(&signature) else {
+        return false;
+    };
+    let message = [timestamp.as_bytes(), body].concat();
+    public_key.verify_strict(&message, &signature).is_ok()
+}
+
+/// Answer an interaction request. Requests that aren't signed by Discord
+/// are refused, as Discord requires.
+async fn receive(
+    State(interactions): State<Arc<Interactions>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let signed = match (
+        header("X-Signature-Ed25519"),
+        header("X-Signature-Timestamp"),
+    ) {
+        (Some(signature), Some(timestamp)) => {
+            verify(&interactions.public_key, signature, timestamp, &body)
+        }
+        _ => false,
+    };
+    if !signed {
+        return (StatusCode::UNAUTHORIZED, "invalid request signature").into_response();
+    }
+
+    match serde_json::from_slice(&body) {
+        Ok(Interaction::Ping(_)) => Json(json!({ "type": PONG })).into_response(),
+        Ok(Interaction::ApplicationCommand(command)) => {
+            Json(respond(interactions, command)).into_response()
+        }
+        Ok(_) => (StatusCode::BAD_REQUEST, "unsupported interaction").into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// The immediate response to a command: a refusal if it was used outside
+/// the guild's allowed channels, else a deferred response, edited by a task
+/// that runs the command.
+fn respond(interactions: Arc<Interactions>, command: ApplicationCommandInteraction) -> Value {
+    let settings = stored_settings(&interactions.storage, command.guild_id);
+
+    let can_manage = command
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild());
+    let channels = &settings.channels;
+    if !channels.is_empty() && !channels.contains(&command.channel_id) && !can_manage {
+        let mentions = channels
+            .iter()
+            .map(|channel| format!("<#{}>", channel))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return json!({
+            "type": CHANNEL_MESSAGE,
+            "data": {
+                "content": format!("Commands can only be used in {} here.", mentions),
+                "flags": EPHEMERAL,
+            },
+        });
+    }
+
+    let ephemeral = settings.ephemeral || command.data.name == "about_name";
+    tokio::spawn(async move {
+        // Discord has its answer already, so commands queue for their turn
+        // rather than being refused.
+        let _turn = http::wait_for_turn().await;
+        let answer = dispatch::run(&interactions.storage, &invocation(&command), settings).await;
+        if let Err(e) = interactions
+            .http
+            .edit_original_interaction_response(&command.token, &message_json(answer.message))
+            .await
+        {
+            error!("At answering /{}: {}", command.data.name, e);
+        }
+    });
+
+    let mut response = json!({ "type": DEFERRED_CHANNEL_MESSAGE });
+    if ephemeral {
+        response["data"] = json!({ "flags": EPHEMERAL });
+    }
+    response
+}
+
+/// The command and its options, with option values as text.
+fn invocation(command: &ApplicationCommandInteraction) -> Invocation {
+    Invocation {
+        command: command.data.name.clone(),
+        options: command
+            .data
+            .options
+            .iter()
+            .filter_map(|option| {
+                let text = match option.value.as_ref()? {
+                    Value::String(text) => text.clone(),
+                    value => value.to_string(),
+                };
+                Some((option.name.clone(), text))
+            })
+            .collect(),
+        user_id: command.user.id,
+        guild_id: command.guild_id,
+        nickname: command
+            .member
+            .as_ref()
+            .and_then(|member| member.nick.clone()),
+        simulated: false,
+    }
+}
+
+/// A message as the JSON Discord takes for editing a response.
+fn message_json(message: MessageContent) -> Value {
+    let embeds = message
+        .embed
+        .map(|embed| {
+            let fields = embed
+                .fields
+                .into_iter()
+                .map(|(name, value, inline)| json!({ "name": name, "value": value, "inline": inline }))
+                .collect::<Vec<_>>();
+            vec![json!({ "title": embed.title, "fields": fields })]
+        })
+        .unwrap_or_default();
+    json!({ "content": message.content, "embeds": embeds })
+}
+
+pub(crate) fn router(interactions: Arc<Interactions>) -> Router {
+    Router::new()
+        .route("/interactions", post(receive))
+        .with_state(interactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+    use poise::serenity_prelude::{GuildId, UserId};
+
+    use super::*;
+
+    const TIMESTAMP: &str = "1700000000";
+    const BODY: &[u8] = br#"{"id":"1","application_id":"2","type":1,"token":"token","version":1}"#;
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&rand::random())
+    }
+
+    fn sign(key: &SigningKey, timestamp: &str, body: &[u8]) -> String {
+        let message = [timestamp.as_bytes(), body].concat();
+        hex::encode(key.sign(&message).to_bytes())
+    }
+
+    #[test]
+    fn accepts_valid_signature() {
+        let key = signing_key();
+        let signature = sign(&key, TIMESTAMP, BODY);
+        assert!(verify(&key.verifying_key(), &signature, TIMESTAMP, BODY));
+    }
+
+    #[test]
+    fn rejects_tampered_body() {
+        let key = signing_key();
+        let signature = sign(&key, TIMESTAMP, BODY);
+        assert!(!verify(
+            &key.verifying_key(),
+            &signature,
+            TIMESTAMP,
+            br#"{"type":2}"#
+        ));
+    }
+
+    #[test]
+    fn rejects_changed_timestamp() {
+        let key = signing_key();
+        let signature = sign(&key, TIMESTAMP, BODY);
+        assert!(!verify(
+            &key.verifying_key(),
+            &signature,
+            "1700000001",
+            BODY
+        ));
+    }
+
+    #[test]
+    fn rejects_other_key() {
+        let signature = sign(&signing_key(), TIMESTAMP, BODY);
+        assert!(!verify(
+            &signing_key().verifying_key(),
+            &signature,
+            TIMESTAMP,
+            BODY
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_signature() {
+        let key = signing_key().verifying_key();
+        assert!(!verify(&key, "not hex", TIMESTAMP, BODY));
+        assert!(!verify(&key, "abcd", TIMESTAMP, BODY));
+        assert!(!verify(&key, "", TIMESTAMP, BODY));
+    }
+
+    #[test]
+    fn parses_public_key() {
+        let key = signing_key().verifying_key();
+        let hex = hex::encode(key.to_bytes());
+        assert_eq!(parse_public_key(&hex), Ok(key));
+        assert_eq!(parse_public_key(&format!(" {}\n", hex)), Ok(key));
+        assert!(parse_public_key("zz").is_err());
+        assert!(parse_public_key(&hex[..62]).is_err());
+    }
+
+    fn interactions(public_key: VerifyingKey) -> Arc<Interactions> {
+        Arc::new(Interactions {
+            http: Arc::new(Http::new("token")),
+            storage: Arc::new(Storage::open(":memory:").unwrap()),
+            public_key,
+        })
+    }
+
+    async fn post(interactions: Arc<Interactions>, signature: &str) -> Response {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Signature-Ed25519", signature.parse().unwrap());
+        headers.insert("X-Signature-Timestamp", TIMESTAMP.parse().unwrap());
+        receive(State(interactions), headers, Bytes::from_static(BODY)).await
+    }
+
+    #[tokio::test]
+    async fn answers_signed_ping() {
+        let key = signing_key();
+        let response = post(
+            interactions(key.verifying_key()),
+            &sign(&key, TIMESTAMP, BODY),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({ "type": 1 })
+        );
+    }
+
+    #[tokio::test]
+    async fn shows_the_seed_of_names() {
+        crate::config::install_test_config();
+        let storage = Storage::open(":memory:").unwrap();
+        let invocation = Invocation {
+            command: "name".into(),
+            options: vec![
+                ("backend".into(), "local".into()),
+                ("seed".into(), "42".into()),
+            ],
+            user_id: UserId(7),
+            guild_id: Some(GuildId(8)),
+            nickname: None,
+            simulated: false,
+        };
+        let answer = dispatch::run(&storage, &invocation, stored_settings(&storage, None)).await;
+        let message = message_json(answer.message);
+        let fields = message["embeds"][0]["fields"].as_array().unwrap();
+        assert!(fields.contains(&json!({ "name": "Seed", "value": "42", "inline": true })));
+    }
+
+    #[tokio::test]
+    async fn refuses_unsigned_ping() {
+        let signature = sign(&signing_key(), TIMESTAMP, BODY);
+        let response = post(interactions(signing_key().verifying_key()), &signature).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,15 @@
+//! Random names from BehindTheName, for Discord and the command line.
+
+mod btn;
+mod commands;
+mod config;
+mod dispatch;
+mod http;
+mod interactions;
+mod naming;
+mod repl;
+mod resources;
+mod storage;
+
+pub mod bot;
+pub mod cli;
@@ -1,45 +1,4 @@
-mod commands;
-mod resources;
-
-use poise::serenity_prelude::GatewayIntents;
-use std::env;
-
-use crate::commands::names::{about_name, debug_name, help_rnc, name};
-use crate::resources::types::Data;
-
 #[tokio::main]
 async fn main() {
-    // This will load the environment variables located at `./.env`, relative to
-    // the CWD. See `./.env.example` for an example on how to structure this.
-    dotenv::dotenv().expect("Failed to load .env file");
-
-    // Initialize the logger to use environment variables.
-    //
-    // In this case, a good default is setting the environment variable
-    // `RUST_LOG` to `debug`.
-    tracing_subscriber::fmt::init();
-
-    let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
-
-    let gateway_intents = GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT;
-
-    let framework = poise::Framework::builder()
-        .options(poise::FrameworkOptions {
-            commands: vec![name(), debug_name(), about_name(), help_rnc()],
-            prefix_options: poise::PrefixFrameworkOptions {
-                prefix: Some("~".into()),
-                ..Default::default()
-            },
-            ..Default::default()
-        })
-        .token(token)
-        .intents(gateway_intents)
-        .setup(|ctx, _ready, framework| {
-            Box::pin(async move {
-                poise::builtins::register_globally(ctx, &framework.options().commands).await?;
-                Ok(Data {})
-            })
-        });
-
-    framework.run().await.unwrap();
+    randomnamecord::bot::run().await;
 }
@@ -0,0 +1,416 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::naming::format::{full_name, name_parts, FormatOptions};
+use crate::resources::types::{Name, ParseSpecError};
+
+/// Requirements a generated name must meet, checked after generation; names
+/// that fail are re-rolled.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Constraints {
+    /// The first name starts with this (ignoring case and accents).
+    prefix: Option<String>,
+    /// Bounds on the length of the first name, in letters.
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    /// Every part of the name is plain ASCII.
+    ascii_only: bool,
+    /// Every letter of the name is one of these (ignoring case and accents).
+    allowed_characters: Option<String>,
+    /// The first name and surname start with the same letter.
+    alliterative: bool,
+    /// Bounds on the number of syllables in the first name.
+    min_syllables: Option<usize>,
+    max_syllables: Option<usize>,
+    /// First and full names already in use, e.g. on a roster, folded.
+    /// These aren't part of the written constraints.
+    taken_first_names: HashSet<String>,
+    taken_full_names: HashSet<String>,
+}
+
+/// Lowercase and strip accents, e.g. "Élodie" to "elodie".
+fn fold(text: &str) -> String {
+    text.to_lowercase()
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect()
+}
+
+fn in_range(value: usize, min: Option<usize>, max: Option<usize>) -> bool {
+    min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+}
+
+/// Estimate syllables by counting vowel groups, not counting a silent
+/// final "e".
+fn syllables(name: &str) -> usize {
+    let letters = fold(name)
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<Vec<_>>();
+    let is_vowel = |c: &char| "aeiouy".contains(*c);
+
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in &letters {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+
+    let silent_e = letters.len() > 2
+        && letters.ends_with(&['e'])
+        && !is_vowel(&letters[letters.len() - 2])
+        && !letters.ends_with(&['l', 'e']);
+    if silent_e && count > 1 {
+        count -= 1;
+    }
+
+    count.max(1)
+}
+
+impl Constraints {
+    /// Reject first names already in use, ignoring case and accents.
+    pub(crate) fn avoid_first_names<'a>(&mut self, first_names: impl IntoIterator<Item = &'a str>) {
+        self.taken_first_names
+            .extend(first_names.into_iter().map(fold));
+    }
+
+    /// Reject full names already in use, in their default order, ignoring
+    /// case and accents.
+    pub(crate) fn avoid_full_names<'a>(&mut self, full_names: impl IntoIterator<Item = &'a str>) {
+        self.taken_full_names
+            .extend(full_names.into_iter().map(fold));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.prefix.is_none()
+            && self.min_length.is_none()
+            && self.max_length.is_none()
+            && !self.ascii_only
+            && self.allowed_characters.is_none()
+            && !self.alliterative
+            && self.min_syllables.is_none()
+            && self.max_syllables.is_none()
+    }
+
+    fn accepts_text(&self, text: &str) -> bool {
+        let allowed = self.allowed_characters.as_ref().is_none_or(|allowed| {
+            fold(text)
+                .chars()
+                .filter(|c| c.is_alphabetic())
+                .all(|c| allowed.contains(c))
+        });
+        allowed && (!self.ascii_only || text.is_ascii())
+    }
+
+    /// Check the constraints that only concern the first name, so that a
+    /// failing name can be re-rolled before looking up its surname.
+    pub(crate) fn accepts_first_name(&self, first_name: &str) -> bool {
+        let length = first_name.chars().filter(|c| c.is_alphabetic()).count();
+
+        self.prefix
+            .as_ref()
+            .is_none_or(|prefix| fold(first_name).starts_with(prefix.as_str()))
+            && in_range(length, self.min_length, self.max_length)
+            && in_range(
+                syllables(first_name),
+                self.min_syllables,
+                self.max_syllables,
+            )
+            && self.accepts_text(first_name)
+            && !self.taken_first_names.contains(&fold(first_name))
+    }
+
+    pub(crate) fn accepts(&self, name: &Name) -> bool {
+        let surnames = name
+            .last_name_result
+            .iter()
+            .chain(&name.second_last_name)
+            .chain(name.patronymic.iter().map(|patronymic| &patronymic.text));
+
+        let alliterative = !self.alliterative
+            || match &name.last_name_result {
+                Ok(last_name) => {
+                    fold(&name.first_name).chars().next() == fold(last_name).chars().next()
+                }
+                Err(_) => false,
+            };
+
+        let untaken = self.taken_full_names.is_empty()
+            || !self.taken_full_names.contains(&fold(&full_name(&name_parts(
+                name,
+                FormatOptions::default(),
+            ))));
+
+        self.accepts_first_name(&name.first_name)
+            && surnames.into_iter().all(|text| self.accepts_text(text))
+            && alliterative
+            && untaken
+    }
+}
+
+impl std::fmt::Display for Constraints {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let range = |min: Option<usize>, max: Option<usize>| match (min, max) {
+            (Some(min), Some(max)) if min == max => format!("{}", min),
+            (min, max) => format!(
+                "{}-{}",
+                min.map(|n| n.to_string()).unwrap_or_default(),
+                max.map(|n| n.to_string()).unwrap_or_default()
+            ),
+        };
+
+        let mut items = vec![];
+        if let Some(prefix) = &self.prefix {
+            items.push(format!("starts:{}", prefix));
+        }
+        if self.min_length.is_some() || self.max_length.is_some() {
+            items.push(format!(
+                "length:{}",
+                range(self.min_length, self.max_length)
+            ));
+        }
+        if self.ascii_only {
+            items.push("ascii".into());
+        }
+        if let Some(allowed) = &self.allowed_characters {
+            items.push(format!("chars:{}", allowed));
+        }
+        if self.alliterative {
+            items.push("alliterative".into());
+        }
+        if self.min_syllables.is_some() || self.max_syllables.is_some() {
+            items.push(format!(
+                "syllables:{}",
+                range(self.min_syllables, self.max_syllables)
+            ));
+        }
+        write!(f, "{}", items.join(" "))
+    }
+}
+
+/// Parse "N", "N-M", "N-" or "-M".
+fn parse_range(item: &str, value: &str) -> Result<(Option<usize>, Option<usize>), ParseSpecError> {
+    let parse = |bound: &str| match bound {
+        "" => Ok(None),
+        bound => bound
+            .parse::<usize>()
+            .map(Some)
+            .map_err(|_| ParseSpecError(format!("Invalid number in {}", item))),
+    };
+
+    let (min, max) = match value.split_once('-') {
+        Some((min, max)) => (parse(min)?, parse(max)?),
+        None => {
+            let n = parse(value)?;
+            (n, n)
+        }
+    };
+
+    match (min, max) {
+        (Some(min), Some(max)) if min > max => {
+            Err(ParseSpecError(format!("Empty range in {}", item)))
+        }
+        (None, None) => Err(ParseSpecError(format!("Missing range in {}", item))),
+        range => Ok(range),
+    }
+}
+
+impl FromStr for Constraints {
+    type Err = ParseSpecError;
+
+    /// Parse a list of constraints, e.g. "starts:K length:-6 alliterative".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut constraints = Constraints::default();
+        for item in s.split_whitespace() {
+            let (key, value) = item.split_once(':').unwrap_or((item, ""));
+            match (key.to_lowercase().as_str(), value) {
+                ("starts" | "prefix", prefix) if !prefix.is_empty() => {
+                    constraints.prefix = Some(fold(prefix))
+                }
+                ("length", range) => {
+                    (constraints.min_length, constraints.max_length) = parse_range(item, range)?
+                }
+                ("syllables", range) => {
+                    (constraints.min_syllables, constraints.max_syllables) =
+                        parse_range(item, range)?
+                }
+                ("chars", allowed) if !allowed.is_empty() => {
+                    constraints.allowed_characters = Some(fold(allowed))
+                }
+                ("ascii", "") => constraints.ascii_only = true,
+                ("alliterative" | "alliteration", "") => constraints.alliterative = true,
+                _ => return Err(ParseSpecError(format!("Unknown constraint {}", item))),
+            }
+        }
+
+        if constraints.is_empty() {
+            Err(ParseSpecError("No constraints given".into()))
+        } else {
+            Ok(constraints)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraints(s: &str) -> Constraints {
+        s.parse().unwrap()
+    }
+
+    fn name(first_name: &str, last_name: Option<&str>) -> Name {
+        Name {
+            first_name: first_name.into(),
+            patronymic: None,
+            last_name_result: last_name.map(String::from).ok_or_else(String::new),
+            second_last_name: None,
+            epithet: None,
+            usage: None,
+            given_usage: None,
+        }
+    }
+
+    #[test]
+    fn parses_ranges() {
+        for (value, range) in [
+            ("3", (Some(3), Some(3))),
+            ("2-5", (Some(2), Some(5))),
+            ("-6", (None, Some(6))),
+            ("4-", (Some(4), None)),
+            ("4-4", (Some(4), Some(4))),
+        ] {
+            assert_eq!(parse_range(value, value).unwrap(), range, "{}", value);
+        }
+        for value in ["", "-", "x", "1-x", "5-2", "1-2-3"] {
+            assert!(parse_range(value, value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn parses_constraints() {
+        let parsed = constraints("Starts:É length:-6 syllables:2- chars:AbC ascii alliteration");
+        assert_eq!(parsed.prefix.as_deref(), Some("e"));
+        assert_eq!((parsed.min_length, parsed.max_length), (None, Some(6)));
+        assert_eq!(
+            (parsed.min_syllables, parsed.max_syllables),
+            (Some(2), None)
+        );
+        assert_eq!(parsed.allowed_characters.as_deref(), Some("abc"));
+        assert!(parsed.ascii_only && parsed.alliterative);
+
+        for s in [
+            "",
+            "starts:",
+            "chars:",
+            "ascii:yes",
+            "length:",
+            "length:9-1",
+            "rhymes",
+        ] {
+            assert!(s.parse::<Constraints>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        for (s, shown) in [
+            ("starts:k", "starts:k"),
+            ("length:3-3", "length:3"),
+            ("length:-6 syllables:2-", "length:-6 syllables:2-"),
+            (
+                "alliterative ascii chars:abc starts:K length:3-6 syllables:1-2",
+                "starts:k length:3-6 ascii chars:abc alliterative syllables:1-2",
+            ),
+        ] {
+            let parsed = constraints(s);
+            assert_eq!(parsed.to_string(), shown, "{}", s);
+            assert_eq!(constraints(shown).to_string(), shown, "{}", s);
+        }
+    }
+
+    #[test]
+    fn counts_vowel_groups() {
+        for (name, count) in [
+            ("Bo", 1),
+            ("Lynn", 1),
+            ("Kate", 1),
+            ("Anna", 2),
+            ("Grace", 1),
+            ("Myrtle", 2),
+            ("Zdzisław", 2),
+            ("Olivia", 3),
+            ("Élodie", 3),
+            ("Ng", 1),
+        ] {
+            assert_eq!(syllables(name), count, "{}", name);
+        }
+    }
+
+    #[test]
+    fn checks_first_names() {
+        for (s, first_name, accepted) in [
+            ("starts:e", "Élodie", true),
+            ("starts:E", "élodie", true),
+            ("starts:el", "Anna", false),
+            ("length:-5", "Élodie", false),
+            ("length:6", "Élodie", true),
+            ("length:2-", "Jo-Ann", true),
+            ("syllables:1", "Kate", true),
+            ("syllables:3-", "Anna", false),
+            ("ascii", "Élodie", false),
+            ("ascii", "Elodie", true),
+            ("chars:elodi", "Élodie", true),
+            ("chars:elod", "Élodie", false),
+        ] {
+            assert_eq!(
+                constraints(s).accepts_first_name(first_name),
+                accepted,
+                "{} {}",
+                s,
+                first_name
+            );
+        }
+    }
+
+    #[test]
+    fn checks_full_names() {
+        for (s, first_name, last_name, accepted) in [
+            ("alliterative", "Anna", Some("Andersen"), true),
+            ("alliterative", "Émile", Some("Eriksson"), true),
+            ("alliterative", "Anna", Some("Berg"), false),
+            ("alliterative", "Anna", None, false),
+            ("ascii", "Anna", Some("Müller"), false),
+            ("ascii", "Anna", None, true),
+            ("chars:anbefg", "Anna", Some("Berg"), false),
+            ("chars:anberg", "Anna", Some("Berg"), true),
+        ] {
+            assert_eq!(
+                constraints(s).accepts(&name(first_name, last_name)),
+                accepted,
+                "{} {} {:?}",
+                s,
+                first_name,
+                last_name
+            );
+        }
+    }
+
+    #[test]
+    fn avoids_taken_names() {
+        let mut constraints = Constraints::default();
+        constraints.avoid_first_names(["élodie"]);
+        constraints.avoid_full_names(["Anna Berg"]);
+        assert!(constraints.is_empty());
+        assert!(!constraints.accepts_first_name("ELODIE"));
+        assert!(constraints.accepts_first_name("Anna"));
+        assert!(!constraints.accepts(&name("Anna", Some("Berg"))));
+        assert!(constraints.accepts(&name("Anna", Some("Andersen"))));
+    }
+}
@@ -0,0 +1,33 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+const EPITHETS: [&str; 24] = [
+    "the Bold",
+    "the Brave",
+    "the Black",
+    "the Elder",
+    "the Fair",
+    "the Great",
+    "the Just",
+    "the Lame",
+    "the Learned",
+    "the Lion",
+    "the Merciful",
+    "the Old",
+    "the Pious",
+    "the Proud",
+    "the Red",
+    "the Silent",
+    "the Stout",
+    "the Tall",
+    "the Unready",
+    "the Wanderer",
+    "the Wise",
+    "the Young",
+    "the Younger",
+    "Ironside",
+];
+
+pub(crate) fn epithet(rng: &mut impl Rng) -> String {
+    EPITHETS.choose(rng).unwrap().to_string()
+}
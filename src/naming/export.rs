@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+
+use crate::naming::format::{full_name, name_parts, FormatOptions, PartKind};
+use crate::naming::links::{first_name_url, hyperlink, last_name_url};
+use crate::resources::types::{ExportFormat, Name};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct PartRecord {
+    pub(crate) kind: PartKind,
+    pub(crate) label: String,
+    pub(crate) text: String,
+    pub(crate) url: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct NameRecord {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) role: Option<String>,
+    pub(crate) full_name: String,
+    pub(crate) usage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) given_usage: Option<String>,
+    pub(crate) parts: Vec<PartRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) notes: Option<String>,
+}
+
+impl NameRecord {
+    pub(crate) fn new(name: &Name, options: FormatOptions, role: Option<String>) -> NameRecord {
+        let parts = name_parts(name, options);
+
+        NameRecord {
+            role,
+            full_name: full_name(&parts),
+            usage: name.usage.as_ref().map(|usage| usage.usage_full.clone()),
+            given_usage: name
+                .given_usage
+                .as_ref()
+                .map(|usage| usage.usage_full.clone()),
+            parts: parts
+                .iter()
+                .map(|part| PartRecord {
+                    kind: part.kind,
+                    label: part.label.into(),
+                    text: part.text.clone(),
+                    url: part.url(),
+                })
+                .collect(),
+            notes: None,
+        }
+    }
+
+    /// A record of a name typed in by hand: a given name followed by
+    /// family names, each linked to BehindTheName.
+    pub(crate) fn typed(text: &str) -> NameRecord {
+        let words = text.split_whitespace().collect::<Vec<_>>();
+        let parts = words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| match index {
+                0 => PartRecord {
+                    kind: PartKind::Given,
+                    label: "First Name".into(),
+                    text: word.to_string(),
+                    url: Some(first_name_url(word)),
+                },
+                _ => PartRecord {
+                    kind: PartKind::Family,
+                    label: "Last Name".into(),
+                    text: word.to_string(),
+                    url: Some(last_name_url(word)),
+                },
+            })
+            .collect();
+
+        NameRecord {
+            role: None,
+            full_name: words.join(" "),
+            usage: None,
+            given_usage: None,
+            parts,
+            notes: None,
+        }
+    }
+
+    /// The name with each part linked to its BehindTheName page.
+    pub(crate) fn links(&self) -> String {
+        self.parts
+            .iter()
+            .map(|part| match &part.url {
+                Some(url) => hyperlink(&part.text, url),
+                None => part.text.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The usages of the name, e.g. "Irish" or "Japanese / Italian".
+    pub(crate) fn usages(&self) -> Option<String> {
+        match (&self.given_usage, &self.usage) {
+            (Some(given_usage), Some(usage)) => Some(format!("{} / {}", given_usage, usage)),
+            (Some(given_usage), None) => Some(format!("{} / none", given_usage)),
+            (None, Some(usage)) => Some(usage.clone()),
+            _ => None,
+        }
+    }
+
+    fn texts_of(&self, kind: PartKind) -> String {
+        self.parts
+            .iter()
+            .filter(|part| part.kind == kind)
+            .map(|part| part.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.into()
+    }
+}
+
+fn to_text(records: &[NameRecord]) -> String {
+    records
+        .iter()
+        .map(|record| {
+            let mut lines = vec![match (&record.role, &record.usage) {
+                (Some(role), Some(usage)) => format!("{}: {} ({})", role, record.full_name, usage),
+                (Some(role), None) => format!("{}: {}", role, record.full_name),
+                (None, Some(usage)) => format!("{} ({})", record.full_name, usage),
+                (None, None) => record.full_name.clone(),
+            }];
+            for part in &record.parts {
+                lines.push(match &part.url {
+                    Some(url) => format!("  {}: {} <{}>", part.label, part.text, url),
+                    None => format!("  {}: {}", part.label, part.text),
+                });
+            }
+            if let Some(notes) = &record.notes {
+                lines.push(format!("  Notes: {}", notes));
+            }
+            lines.join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn to_csv(records: &[NameRecord]) -> String {
+    let mut lines = vec![
+        "role,full_name,usage,given_usage,given_name,patronymic,family_name,epithet,urls,notes"
+            .to_string(),
+    ];
+    for record in records {
+        let urls = record
+            .parts
+            .iter()
+            .filter_map(|part| part.url.as_deref())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let fields = [
+            record.role.clone().unwrap_or_default(),
+            record.full_name.clone(),
+            record.usage.clone().unwrap_or_default(),
+            record.given_usage.clone().unwrap_or_default(),
+            record.texts_of(PartKind::Given),
+            record.texts_of(PartKind::Patronymic),
+            record.texts_of(PartKind::Family),
+            record.texts_of(PartKind::Epithet),
+            urls,
+            record.notes.clone().unwrap_or_default(),
+        ];
+        lines.push(
+            fields
+                .iter()
+                .map(|f| csv_field(f))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    }
+    lines.join("\n")
+}
+
+/// Render name records in the given format, returning the file contents and
+/// the file extension to use.
+pub(crate) fn export(records: &[NameRecord], format: ExportFormat) -> (String, &'static str) {
+    match format {
+        ExportFormat::Text => (to_text(records), "txt"),
+        ExportFormat::Csv => (to_csv(records), "csv"),
+        ExportFormat::Json => (serde_json::to_string_pretty(records).unwrap(), "json"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_csv_fields() {
+        for (field, quoted) in [
+            ("Ada", "Ada"),
+            ("Lovelace, Ada", "\"Lovelace, Ada\""),
+            ("\"Ada\"", "\"\"\"Ada\"\"\""),
+            ("line\nbreak", "\"line\nbreak\""),
+            ("line\r\nbreak", "\"line\r\nbreak\""),
+            ("carriage\rreturn", "\"carriage\rreturn\""),
+        ] {
+            assert_eq!(csv_field(field), quoted, "{:?}", field);
+        }
+    }
+
+    #[test]
+    fn writes_both_usages_to_csv() {
+        let mut record = NameRecord::typed("Siobhan Rossi");
+        record.usage = Some("Italian".into());
+        record.given_usage = Some("Irish".into());
+        record.notes = Some("Met in\r\nRome".into());
+        let csv = to_csv(&[record]);
+        let (header, row) = csv.split_once('\n').unwrap();
+        assert_eq!(
+            header,
+            "role,full_name,usage,given_usage,given_name,patronymic,family_name,epithet,urls,notes"
+        );
+        assert!(row.starts_with(",Siobhan Rossi,Italian,Irish,Siobhan,,Rossi,,"));
+        assert!(row.ends_with(",\"Met in\r\nRome\""));
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use behindthename::types::Usage;
+use serde_json::json;
+
+use crate::resources::types::ParseSpecError;
+use crate::resources::usages::{era_of, Era};
+
+/// Usage codes and catalog groups (eras) to keep out of generated names.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Exclusions {
+    codes: BTreeSet<String>,
+    eras: BTreeSet<Era>,
+}
+
+impl Exclusions {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.codes.is_empty() && self.eras.is_empty()
+    }
+
+    pub(crate) fn excludes(&self, usage_code: &str) -> bool {
+        self.codes.contains(usage_code)
+            || era_of(usage_code).is_some_and(|era| self.eras.contains(&era))
+    }
+
+    /// Whether a name with these usages should be rejected, i.e. all of its
+    /// (known) usages are excluded.
+    pub(crate) fn rejects(&self, usages: &[Usage]) -> bool {
+        !usages.is_empty() && usages.iter().all(|usage| self.excludes(&usage.usage_code))
+    }
+
+    pub(crate) fn union(&self, other: &Exclusions) -> Exclusions {
+        Exclusions {
+            codes: self.codes.union(&other.codes).cloned().collect(),
+            eras: self.eras.union(&other.eras).cloned().collect(),
+        }
+    }
+
+    pub(crate) fn difference(&self, other: &Exclusions) -> Exclusions {
+        Exclusions {
+            codes: self.codes.difference(&other.codes).cloned().collect(),
+            eras: self.eras.difference(&other.eras).cloned().collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for Exclusions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items = self
+            .eras
+            .iter()
+            .map(|era| era_name(*era))
+            .chain(self.codes.iter().cloned())
+            .collect::<Vec<_>>();
+        write!(f, "{}", items.join(" "))
+    }
+}
+
+fn era_name(era: Era) -> String {
+    serde_json::to_value(era)
+        .unwrap()
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+impl FromStr for Exclusions {
+    type Err = ParseSpecError;
+
+    /// Parse a list of usage codes and group names, e.g. "mythology bibl".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut exclusions = Exclusions::default();
+        for item in s
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|item| !item.is_empty())
+            .map(|item| item.to_lowercase())
+        {
+            match serde_json::from_value::<Era>(json!(item)) {
+                Ok(era) => exclusions.eras.insert(era),
+                Err(_) => exclusions.codes.insert(item),
+            };
+        }
+
+        if exclusions.is_empty() {
+            Err(ParseSpecError("No usages given".into()))
+        } else {
+            Ok(exclusions)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use behindthename::types::Gender;
+
+    use super::*;
+    use crate::resources::usages::usage_for;
+
+    fn exclusions(s: &str) -> Exclusions {
+        s.parse().unwrap()
+    }
+
+    fn usages(codes: &[&str]) -> Vec<Usage> {
+        codes
+            .iter()
+            .map(|code| usage_for(code, Gender::Any))
+            .collect()
+    }
+
+    #[test]
+    fn parses_codes_and_groups() {
+        let parsed = exclusions("Mythology, bibl  eng");
+        assert_eq!(parsed.eras, BTreeSet::from([Era::Mythology]));
+        assert_eq!(
+            parsed.codes,
+            BTreeSet::from(["bibl".to_string(), "eng".to_string()])
+        );
+        assert!("".parse::<Exclusions>().is_err());
+        assert!(" , ".parse::<Exclusions>().is_err());
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        for s in [
+            "mythology",
+            "eng fre",
+            "medieval biblical ita",
+            "mythology eng-bibl",
+        ] {
+            let parsed = exclusions(s);
+            assert_eq!(exclusions(&parsed.to_string()), parsed, "{}", s);
+        }
+        assert_eq!(exclusions("eng medieval").to_string(), "medieval eng");
+    }
+
+    #[test]
+    fn combines_exclusions() {
+        let server = exclusions("mythology eng");
+        let user = exclusions("biblical eng fre");
+        assert_eq!(
+            server.union(&user),
+            exclusions("mythology biblical eng fre")
+        );
+        assert_eq!(server.difference(&user), exclusions("mythology"));
+        assert!(server.difference(&server).is_empty());
+    }
+
+    #[test]
+    fn expands_groups() {
+        let mythology = exclusions("mythology");
+        assert!(mythology.excludes("gre-myth"));
+        assert!(mythology.excludes("nor-myth"));
+        // Codes missing from the catalog fall back on their suffix.
+        assert!(mythology.excludes("ind-myth"));
+        assert!(!mythology.excludes("gre"));
+
+        let medieval = exclusions("medieval");
+        assert!(medieval.excludes("eng-medi"));
+        assert!(!medieval.excludes("eng"));
+
+        let biblical = exclusions("biblical");
+        assert!(biblical.excludes("bibl"));
+        assert!(biblical.excludes("eng-bibl"));
+        assert!(!biblical.excludes("heb"));
+    }
+
+    #[test]
+    fn rejects_names_only_when_every_usage_is_excluded() {
+        let excluded = exclusions("mythology eng");
+        assert!(excluded.excludes("eng"));
+        assert!(!excluded.excludes("fre"));
+        assert!(excluded.rejects(&usages(&["eng", "gre-myth"])));
+        assert!(!excluded.rejects(&usages(&["eng", "fre"])));
+        // Names whose usages are unknown are kept.
+        assert!(!excluded.rejects(&[]));
+    }
+}
@@ -0,0 +1,402 @@
+use serde::{Deserialize, Serialize};
+
+use crate::naming::links::{first_name_url, hyperlink, last_name_url};
+use crate::resources::types::Name;
+use crate::resources::usages::{self, NameOrder};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PartKind {
+    Given,
+    Patronymic,
+    Family,
+    Epithet,
+}
+
+pub(crate) struct NamePart {
+    pub(crate) kind: PartKind,
+    pub(crate) label: &'static str,
+    pub(crate) text: String,
+    /// The name to look up on BehindTheName for this part.
+    pub(crate) lookup: String,
+    /// A word written before this part in the full name, e.g. "y" between
+    /// Spanish surnames.
+    pub(crate) prefix: Option<&'static str>,
+}
+
+impl NamePart {
+    pub(crate) fn url(&self) -> Option<String> {
+        match self.kind {
+            PartKind::Given | PartKind::Patronymic => Some(first_name_url(&self.lookup)),
+            PartKind::Family => Some(last_name_url(&self.lookup)),
+            PartKind::Epithet => None,
+        }
+    }
+
+    /// The part as a hyperlink to BehindTheName, or as plain text if it has
+    /// no page there.
+    pub(crate) fn link(&self) -> String {
+        match self.url() {
+            Some(url) => hyperlink(&self.text, &url),
+            None => self.text.clone(),
+        }
+    }
+
+    fn new(kind: PartKind, label: &'static str, text: &str) -> NamePart {
+        NamePart {
+            kind,
+            label,
+            text: text.into(),
+            lookup: text.into(),
+            prefix: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct FormatOptions {
+    /// Always put the given name first.
+    pub(crate) western_order: bool,
+    /// Join double surnames with their conjunction.
+    pub(crate) surname_conjunction: bool,
+}
+
+/// Pick the order to display a name in: the convention of the name's usage,
+/// unless Western order is forced.
+pub(crate) fn name_order(name: &Name, options: FormatOptions) -> NameOrder {
+    match (&name.usage, options.western_order) {
+        (Some(usage), false) => usages::name_order(&usage.usage_code),
+        _ => NameOrder::GivenFirst,
+    }
+}
+
+fn label(kind: PartKind, order: NameOrder) -> &'static str {
+    match (kind, order) {
+        (PartKind::Given, NameOrder::GivenFirst) => "First Name",
+        (PartKind::Family, NameOrder::GivenFirst) => "Last Name",
+        (PartKind::Given, NameOrder::FamilyFirst) => "Given Name",
+        (PartKind::Family, NameOrder::FamilyFirst) => "Family Name",
+        (PartKind::Patronymic, _) => "Patronymic",
+        (PartKind::Epithet, _) => "Epithet",
+    }
+}
+
+fn family_parts(name: &Name, order: NameOrder, options: FormatOptions) -> Vec<NamePart> {
+    let last_name = match &name.last_name_result {
+        Ok(last_name) => last_name,
+        Err(_) => return vec![],
+    };
+
+    let style = name
+        .usage
+        .as_ref()
+        .and_then(|usage| usages::double_surname_style(&usage.usage_code));
+
+    match (&name.second_last_name, style) {
+        (Some(second_last_name), Some(style)) => {
+            let paternal = NamePart::new(PartKind::Family, "Paternal Surname", last_name);
+            let maternal = NamePart::new(PartKind::Family, "Maternal Surname", second_last_name);
+            let mut parts = if style.maternal_first() {
+                vec![maternal, paternal]
+            } else {
+                vec![paternal, maternal]
+            };
+            if options.surname_conjunction {
+                parts[1].prefix = Some(style.conjunction());
+            }
+            parts
+        }
+        _ => vec![NamePart::new(
+            PartKind::Family,
+            label(PartKind::Family, order),
+            last_name,
+        )],
+    }
+}
+
+/// Split a name into its labelled parts, in display order.
+pub(crate) fn name_parts(name: &Name, options: FormatOptions) -> Vec<NamePart> {
+    let order = name_order(name, options);
+
+    let mut parts = vec![NamePart::new(
+        PartKind::Given,
+        label(PartKind::Given, order),
+        &name.first_name,
+    )];
+
+    if let Some(patronymic) = &name.patronymic {
+        parts.push(NamePart {
+            lookup: patronymic.parent_name.clone(),
+            ..NamePart::new(
+                PartKind::Patronymic,
+                if patronymic.matronymic {
+                    "Matronymic"
+                } else {
+                    label(PartKind::Patronymic, order)
+                },
+                &patronymic.text,
+            )
+        });
+    }
+
+    if let Some(epithet) = &name.epithet {
+        parts.push(NamePart::new(
+            PartKind::Epithet,
+            label(PartKind::Epithet, order),
+            epithet,
+        ));
+    }
+
+    let mut family = family_parts(name, order, options);
+    match order {
+        NameOrder::GivenFirst => {
+            parts.append(&mut family);
+            parts
+        }
+        NameOrder::FamilyFirst => {
+            family.append(&mut parts);
+            family
+        }
+    }
+}
+
+pub(crate) fn full_name(parts: &[NamePart]) -> String {
+    parts
+        .iter()
+        .map(|part| match part.prefix {
+            Some(prefix) => format!("{} {}", prefix, part.text),
+            None => part.text.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Discord's limit on the length of a nickname.
+pub(crate) const MAX_NICKNAME_LENGTH: usize = 32;
+
+/// Fit a name into a nickname, dropping its least essential parts first:
+/// patronymics, epithets and second surnames, then all but the surname's
+/// initial, then the surname.
+pub(crate) fn nickname(name: &Name, options: FormatOptions) -> String {
+    let parts = name_parts(name, options);
+    let surname = parts.iter().find(|part| part.kind == PartKind::Family);
+
+    let essential = parts
+        .iter()
+        .filter(|part| {
+            part.kind == PartKind::Given
+                || surname.is_some_and(|surname| std::ptr::eq(*part, surname))
+        })
+        .map(|part| part.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let initial = surname
+        .and_then(|surname| surname.text.chars().next())
+        .map(|initial| format!("{} {}.", name.first_name, initial));
+
+    [
+        Some(full_name(&parts)),
+        Some(essential),
+        initial,
+        Some(name.first_name.clone()),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|candidate| candidate.chars().count() <= MAX_NICKNAME_LENGTH)
+    .unwrap_or_else(|| name.first_name.chars().take(MAX_NICKNAME_LENGTH).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use behindthename::types::Gender;
+
+    use super::*;
+    use crate::naming::patronymic::Patronymic;
+    use crate::resources::usages::usage_for;
+
+    /// A name of a usage, with a second surname if one is given.
+    fn name(code: &str, first_name: &str, last_name: &str, second: Option<&str>) -> Name {
+        Name {
+            first_name: first_name.into(),
+            patronymic: None,
+            last_name_result: Ok(last_name.into()),
+            second_last_name: second.map(String::from),
+            epithet: None,
+            usage: Some(usage_for(code, Gender::Any)),
+            given_usage: None,
+        }
+    }
+
+    fn shown(name: &Name, options: FormatOptions) -> String {
+        full_name(&name_parts(name, options))
+    }
+
+    fn labels(name: &Name, options: FormatOptions) -> Vec<&'static str> {
+        name_parts(name, options)
+            .iter()
+            .map(|part| part.label)
+            .collect()
+    }
+
+    #[test]
+    fn orders_names_per_culture() {
+        for (code, first_name, last_name, expected) in [
+            ("eng", "Ada", "Lovelace", "Ada Lovelace"),
+            ("hun", "Ferenc", "Molnár", "Molnár Ferenc"),
+            ("chi", "Wei", "Zhang", "Zhang Wei"),
+            ("jap", "Haruki", "Murakami", "Murakami Haruki"),
+            ("kor", "Ji-woo", "Kim", "Kim Ji-woo"),
+            ("vie", "Lan", "Nguyen", "Nguyen Lan"),
+            ("xyz", "Ada", "Lovelace", "Ada Lovelace"),
+        ] {
+            let name = name(code, first_name, last_name, None);
+            assert_eq!(shown(&name, FormatOptions::default()), expected, "{}", code);
+        }
+    }
+
+    #[test]
+    fn labels_family_first_names() {
+        let options = FormatOptions::default();
+        assert_eq!(
+            labels(&name("jap", "Haruki", "Murakami", None), options),
+            ["Family Name", "Given Name"]
+        );
+        assert_eq!(
+            labels(&name("eng", "Ada", "Lovelace", None), options),
+            ["First Name", "Last Name"]
+        );
+    }
+
+    #[test]
+    fn western_order_puts_the_given_name_first() {
+        let options = FormatOptions {
+            western_order: true,
+            ..Default::default()
+        };
+        let name = name("jap", "Haruki", "Murakami", None);
+        assert_eq!(shown(&name, options), "Haruki Murakami");
+        assert_eq!(labels(&name, options), ["First Name", "Last Name"]);
+    }
+
+    #[test]
+    fn shows_patronymics_and_epithets_after_the_given_name() {
+        let mut ivan = name("rus", "Ivan", "Petrov", None);
+        ivan.patronymic = Some(Patronymic {
+            parent_name: "Sergei".into(),
+            text: "Sergeevich".into(),
+            matronymic: false,
+        });
+        assert_eq!(
+            shown(&ivan, FormatOptions::default()),
+            "Ivan Sergeevich Petrov"
+        );
+        assert_eq!(
+            name_parts(&ivan, FormatOptions::default())[1].lookup,
+            "Sergei"
+        );
+
+        let mut erik = name("eng-medi", "Erik", "", None);
+        erik.last_name_result = Err("No surname".into());
+        erik.epithet = Some("the Bold".into());
+        assert_eq!(shown(&erik, FormatOptions::default()), "Erik the Bold");
+    }
+
+    #[test]
+    fn orders_double_surnames() {
+        let options = FormatOptions::default();
+        let spanish = name("spa", "Lucía", "García", Some("López"));
+        assert_eq!(shown(&spanish, options), "Lucía García López");
+        assert_eq!(
+            labels(&spanish, options),
+            ["First Name", "Paternal Surname", "Maternal Surname"]
+        );
+
+        // Portuguese names put the mother's surname first.
+        let portuguese = name("por", "João", "Silva", Some("Costa"));
+        assert_eq!(shown(&portuguese, options), "João Costa Silva");
+        assert_eq!(
+            labels(&portuguese, options),
+            ["First Name", "Maternal Surname", "Paternal Surname"]
+        );
+
+        // A second surname is only shown for usages that carry two.
+        let english = name("eng", "Ada", "Lovelace", Some("Byron"));
+        assert_eq!(shown(&english, options), "Ada Lovelace");
+    }
+
+    #[test]
+    fn joins_double_surnames_with_their_conjunction() {
+        let options = FormatOptions {
+            surname_conjunction: true,
+            ..Default::default()
+        };
+        for (code, first, paternal, maternal, expected) in [
+            ("spa", "Lucía", "García", "López", "Lucía García y López"),
+            ("cat", "Jordi", "Puig", "Vidal", "Jordi Puig i Vidal"),
+            ("por", "João", "Silva", "Costa", "João Costa e Silva"),
+        ] {
+            let name = name(code, first, paternal, Some(maternal));
+            assert_eq!(shown(&name, options), expected, "{}", code);
+        }
+        let english = name("eng", "Ada", "Lovelace", None);
+        assert_eq!(shown(&english, options), "Ada Lovelace");
+    }
+
+    #[test]
+    fn shortens_nicknames() {
+        let mut konstantin = name("rus", "Konstantin", "Rozhdestvensky", None);
+        konstantin.patronymic = Some(Patronymic {
+            parent_name: "Konstantin".into(),
+            text: "Konstantinovich".into(),
+            matronymic: false,
+        });
+        let long_first_name = "Wolfeschlegelsteinhausenbergerdorff";
+
+        for (name, expected) in [
+            (name("eng", "Ada", "Lovelace", None), "Ada Lovelace"),
+            (name("jap", "Haruki", "Murakami", None), "Murakami Haruki"),
+            // The patronymic goes first.
+            (konstantin, "Konstantin Rozhdestvensky"),
+            // Then the second surname.
+            (
+                name("spa", "María", "Fernández-Castellanos", Some("Villalobos")),
+                "María Fernández-Castellanos",
+            ),
+            // Then all but the surname's initial.
+            (
+                name(
+                    "spa",
+                    "Maximiliano",
+                    "Bustamante-Santisteban",
+                    Some("Villavicencio"),
+                ),
+                "Maximiliano B.",
+            ),
+            // Then the surname, and what's left of the given name.
+            (
+                name("eng", long_first_name, "Smith", None),
+                &long_first_name[..MAX_NICKNAME_LENGTH],
+            ),
+        ] {
+            let shortened = nickname(&name, FormatOptions::default());
+            assert_eq!(shortened, expected, "{}", name.first_name);
+            assert!(shortened.chars().count() <= MAX_NICKNAME_LENGTH);
+        }
+    }
+
+    #[test]
+    fn counts_nickname_length_in_characters() {
+        // 32 characters, but more bytes.
+        let name = name("fre", "Éléonore-Bénédicte", "Châteaubriand", None);
+        let shortened = nickname(&name, FormatOptions::default());
+        assert!(shortened.len() > MAX_NICKNAME_LENGTH);
+        assert_eq!(shortened, "Éléonore-Bénédicte Châteaubriand");
+
+        let long_first_name = "Ñ".repeat(40);
+        let name = self::name("spa", &long_first_name, "Núñez", None);
+        assert_eq!(
+            nickname(&name, FormatOptions::default()),
+            "Ñ".repeat(MAX_NICKNAME_LENGTH)
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use unicode_normalization::UnicodeNormalization;
+
+use crate::resources::maps::{NORM_AC, NORM_CODES};
+
+pub(crate) fn hyperlink(title: &str, url: &str) -> String {
+    format!("[{}]({})", title, url)
+}
+
+fn lower_normalize(name: &str) -> String {
+    let nfc_name = name.to_lowercase().nfc().to_string();
+
+    NORM_AC.replace_all(&nfc_name, &NORM_CODES)
+}
+
+pub(crate) fn first_name_url(first_name: &str) -> String {
+    format!(
+        "https://www.behindthename.com/name/{}",
+        lower_normalize(first_name)
+    )
+}
+
+pub(crate) fn last_name_url(last_name: &str) -> String {
+    format!(
+        "https://surnames.behindthename.com/name/{}",
+        lower_normalize(last_name)
+    )
+}
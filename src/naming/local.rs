@@ -0,0 +1,255 @@
+use behindthename::types::Gender;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::naming::{patronymic, surname};
+use crate::resources::names::{local_names, local_usage_codes, LocalNames, LOCAL_NAMES};
+use crate::resources::types::{GenMode, Name, NameOptions, UsageSpec};
+use crate::resources::usages::{double_surname_style, patronymic_style, usage_for};
+
+/// The most names to try for one that meets the constraints. Local names
+/// cost no requests, so this stands in for the request budget.
+const MAX_TRIES: usize = 1000;
+
+fn pick(names: &[String], rng: &mut impl Rng) -> Result<String, String> {
+    names
+        .choose(rng)
+        .cloned()
+        .ok_or_else(|| "No names to pick from".into())
+}
+
+fn given_names(names: &LocalNames, gender: Gender) -> &[String] {
+    match gender {
+        Gender::Female => &names.feminine,
+        _ => &names.masculine,
+    }
+}
+
+fn settle_gender(rng: &mut impl Rng, gender: Gender) -> Gender {
+    match gender {
+        Gender::Male | Gender::Female => gender,
+        _ if rng.gen_bool(0.5) => Gender::Male,
+        _ => Gender::Female,
+    }
+}
+
+/// Generate a name from the bundled name lists, making every choice with
+/// `rng`, so that the same seed always gives the same name.
+pub(crate) fn local_name(
+    rng: &mut impl Rng,
+    gender: Gender,
+    usage_code: Option<&str>,
+) -> Result<Name, String> {
+    let names = match usage_code {
+        Some(code) => {
+            local_names(code).ok_or_else(|| format!("No local names for usage {}", code))?
+        }
+        None => LOCAL_NAMES.choose(rng).ok_or("No local names")?,
+    };
+    let code = names.usage.as_str();
+
+    let gender = settle_gender(rng, gender);
+
+    let first_name = pick(given_names(names, gender), rng)?;
+
+    let style = patronymic_style(code);
+    let patronymic = match style {
+        Some(style) => {
+            let father = pick(&names.masculine, rng)?;
+            Some(patronymic::derive(style, father, gender, false, rng))
+        }
+        None => None,
+    };
+
+    let last_name_result = match style {
+        Some(style) if style.replaces_surname() => Err(format!(
+            "{} names use no surname",
+            usage_for(code, gender).usage_full
+        )),
+        _ => pick(&names.surnames, rng)
+            .map(|last_name| surname::gendered_for(code, &last_name, gender)),
+    };
+
+    let second_last_name = match double_surname_style(code) {
+        Some(_) => Some(pick(&names.surnames, rng)?),
+        None => None,
+    };
+
+    Ok(Name {
+        first_name,
+        patronymic,
+        last_name_result,
+        second_last_name,
+        epithet: None,
+        usage: Some(usage_for(code, gender)),
+        given_usage: None,
+    })
+}
+
+/// A given name of one usage with the surname of another, or of the same
+/// one, shown with both usages as BehindTheName's mixed names are.
+fn mixed_name(
+    rng: &mut impl Rng,
+    gender: Gender,
+    given_usage_code: &str,
+    surname_usage_code: &str,
+) -> Result<Name, String> {
+    let given = local_name(rng, gender, Some(given_usage_code))?;
+    let name = local_name(rng, gender, Some(surname_usage_code))?;
+    Ok(Name {
+        first_name: given.first_name,
+        given_usage: given.usage,
+        ..name
+    })
+}
+
+/// Generate a name for `options` from the bundled name lists, as `_name`
+/// does from BehindTheName. Every choice is made with a generator seeded
+/// from `options.seed`, so the same options always give the same name.
+pub(crate) fn generate(options: &NameOptions) -> Result<Name, String> {
+    let mut rng = ChaCha8Rng::seed_from_u64(options.seed);
+    let rng = &mut rng;
+    let exclusions = &options.exclusions;
+    let gender = settle_gender(rng, options.gender.unwrap_or(Gender::Any));
+
+    let usages = local_usage_codes()
+        .into_iter()
+        .filter(|code| !exclusions.excludes(code))
+        .collect::<Vec<_>>();
+    let any_usage = |rng: &mut ChaCha8Rng| {
+        usages
+            .choose(rng)
+            .map(|code| code.to_string())
+            .ok_or_else(|| "All of the local usages are excluded".to_string())
+    };
+    let choose = |spec: &Option<UsageSpec>, rng: &mut ChaCha8Rng| {
+        spec.as_ref()
+            .map(|spec| spec.choose(exclusions, rng))
+            .transpose()
+    };
+
+    for _ in 0..MAX_TRIES {
+        let name = if options.given_usage.is_some() || options.surname_usage.is_some() {
+            let given = choose(&options.given_usage, rng)?;
+            let surname = choose(&options.surname_usage, rng)?;
+            let given = given.or_else(|| surname.clone()).unwrap();
+            let surname = surname.unwrap_or_else(|| given.clone());
+            mixed_name(rng, gender, &given, &surname)?
+        } else {
+            match options.mode.unwrap_or(GenMode::Coherent) {
+                GenMode::Coherent => {
+                    let code = any_usage(rng)?;
+                    local_name(rng, gender, Some(&code))?
+                }
+                GenMode::Chaotic => {
+                    let (given, surname) = (any_usage(rng)?, any_usage(rng)?);
+                    Name {
+                        usage: None,
+                        given_usage: None,
+                        ..mixed_name(rng, gender, &given, &surname)?
+                    }
+                }
+                mode => return Err(format!("There are no local names for {} mode", mode)),
+            }
+        };
+        if options.constraints.accepts(&name) {
+            return Ok(name);
+        }
+    }
+    Err(format!(
+        "No name satisfied the constraints within {} tries",
+        MAX_TRIES
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What a name shows, to compare names drawn with the same options.
+    fn shown(name: &Name) -> String {
+        format!(
+            "{} {:?} {:?} {:?} {:?} {:?}",
+            name.first_name,
+            name.patronymic.as_ref().map(|patronymic| &patronymic.text),
+            name.last_name_result,
+            name.second_last_name,
+            name.usage.as_ref().map(|usage| &usage.usage_code),
+            name.given_usage.as_ref().map(|usage| &usage.usage_code),
+        )
+    }
+
+    fn options(s: &str, seed: u64) -> NameOptions {
+        let mut options = NameOptions {
+            seed,
+            ..Default::default()
+        };
+        for item in s.split(';').filter(|item| !item.is_empty()) {
+            let (key, value) = item.split_once('=').unwrap();
+            match key {
+                "gender" => options.gender = Some(value.parse().unwrap()),
+                "mode" => options.mode = Some(value.parse().unwrap()),
+                "given" => options.given_usage = Some(value.parse().unwrap()),
+                "surname" => options.surname_usage = Some(value.parse().unwrap()),
+                "exclude" => options.exclusions = value.parse().unwrap(),
+                "constraints" => options.constraints = value.parse().unwrap(),
+                _ => panic!("Unknown option {}", key),
+            }
+        }
+        options
+    }
+
+    #[test]
+    fn repeats_names_for_a_seed() {
+        for s in [
+            "",
+            "gender=f",
+            "mode=chaotic",
+            "given=iri:3 ita;surname=jap",
+            "surname=rus;gender=m",
+            "exclude=eng fre ger;constraints=length:-6",
+        ] {
+            for seed in [0, 42, u64::MAX] {
+                let first = generate(&options(s, seed)).unwrap();
+                let second = generate(&options(s, seed)).unwrap();
+                assert_eq!(shown(&first), shown(&second), "{} {}", s, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn draws_different_names_for_different_seeds() {
+        let names = (0..20)
+            .map(|seed| shown(&generate(&options("", seed)).unwrap()))
+            .collect::<std::collections::HashSet<_>>();
+        assert!(names.len() > 1);
+    }
+
+    #[test]
+    fn follows_the_options() {
+        for seed in 0..20 {
+            let name = generate(&options("given=ita;surname=jap;gender=f", seed)).unwrap();
+            assert_eq!(name.usage.unwrap().usage_code, "jap");
+            assert_eq!(name.given_usage.unwrap().usage_code, "ita");
+            assert!(local_names("ita")
+                .unwrap()
+                .feminine
+                .contains(&name.first_name));
+
+            let name = generate(&options("exclude=ara chi dut eng fre ger gre hun ice iri ita jap kor pol por rus sco spa swe ukr", seed)).unwrap();
+            assert_eq!(name.usage.unwrap().usage_code, "wel");
+
+            let name = generate(&options("constraints=starts:k", seed)).unwrap();
+            assert!(name.first_name.starts_with('K'), "{}", name.first_name);
+        }
+    }
+
+    #[test]
+    fn refuses_what_it_has_no_names_for() {
+        assert!(generate(&options("mode=medieval", 0)).is_err());
+        assert!(generate(&options("given=xyz", 0)).is_err());
+        assert!(generate(&options("given=ita;exclude=ita", 0)).is_err());
+        assert!(generate(&options("constraints=chars:q", 0)).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+pub(crate) mod constraints;
+pub(crate) mod era;
+pub(crate) mod export;
+pub(crate) mod filter;
+pub(crate) mod format;
+pub(crate) mod links;
+pub(crate) mod local;
+pub(crate) mod patronymic;
+pub(crate) mod surname;
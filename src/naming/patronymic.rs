@@ -0,0 +1,282 @@
+use behindthename::types::Gender;
+use rand::Rng;
+
+use crate::resources::usages::PatronymicStyle;
+
+pub(crate) struct Patronymic {
+    pub(crate) parent_name: String,
+    pub(crate) text: String,
+    pub(crate) matronymic: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Form {
+    Masculine,
+    Feminine,
+    Neutral,
+}
+
+impl PatronymicStyle {
+    /// Whether the patronymic is used in place of a surname.
+    pub(crate) fn replaces_surname(self) -> bool {
+        self == PatronymicStyle::Icelandic
+    }
+
+    /// Whether the name may be derived from the mother's name instead.
+    pub(crate) fn allows_matronymic(self) -> bool {
+        self == PatronymicStyle::Icelandic
+    }
+
+    fn has_neutral_form(self) -> bool {
+        self == PatronymicStyle::Icelandic
+    }
+}
+
+fn form(style: PatronymicStyle, gender: Gender, rng: &mut impl Rng) -> Form {
+    match gender {
+        Gender::Male => Form::Masculine,
+        Gender::Female => Form::Feminine,
+        _ if style.has_neutral_form() => Form::Neutral,
+        _ if rng.gen_bool(0.5) => Form::Masculine,
+        _ => Form::Feminine,
+    }
+}
+
+fn ends_with_vowel(stem: &str) -> bool {
+    stem.ends_with(['a', 'e', 'i', 'o', 'u', 'y'])
+}
+
+/// Russian names ending in a soft consonant, whose soft sign is usually
+/// left out in transliteration.
+const RUSSIAN_SOFT_STEMS: [&str; 3] = ["Igor", "Lazar", "Kir"];
+/// Russian names in -a whose feminine patronymic keeps an -in- before the
+/// suffix, e.g. Ilya, Ilyinichna.
+const RUSSIAN_IN_STEMS: [&str; 4] = ["Ilya", "Foma", "Kuzma", "Luka"];
+
+fn russian(father: &str, form: Form) -> String {
+    let feminine = form == Form::Feminine;
+    let (hard, soft, short) = if feminine {
+        ("ovna", "evna", "ichna")
+    } else {
+        ("ovich", "evich", "ich")
+    };
+    let soft_sign = father.ends_with('\'');
+    let father = father.trim_end_matches('\'');
+
+    match father {
+        "Pavel" => return format!("Pavl{}", hard),
+        "Lev" => return format!("Lv{}", hard),
+        "Pyotr" => return format!("Petr{}", hard),
+        "Yakov" => return format!("Yakovl{}", soft),
+        _ => (),
+    }
+
+    if soft_sign || RUSSIAN_SOFT_STEMS.contains(&father) {
+        format!("{}{}", father, soft)
+    } else if let Some(stem) = father.strip_suffix('a') {
+        if feminine && RUSSIAN_IN_STEMS.contains(&father) {
+            format!("{}inichna", stem)
+        } else {
+            format!("{}{}", stem, short)
+        }
+    } else if let Some(stem) = father.strip_suffix(['i', 'y']) {
+        if ends_with_vowel(stem) {
+            format!("{}{}", stem, soft)
+        } else {
+            format!("{}{}", father, soft)
+        }
+    } else {
+        format!("{}{}", father, hard)
+    }
+}
+
+fn ukrainian(father: &str, form: Form) -> String {
+    let feminine = form == Form::Feminine;
+    let suffix = if feminine { "ivna" } else { "ovych" };
+
+    if let Some(stem) = father.strip_suffix("ia").or(father.strip_suffix("ya")) {
+        format!("{}i{}", stem, if feminine { "vna" } else { "ch" })
+    } else if let Some(stem) = father.strip_suffix('a') {
+        format!("{}ay{}", stem, suffix)
+    } else if let Some(stem) = father.strip_suffix('o') {
+        format!("{}{}", stem, suffix)
+    } else {
+        format!("{}{}", father, suffix)
+    }
+}
+
+fn icelandic_genitive(parent: &str, matronymic: bool) -> String {
+    match parent {
+        "Hörður" => return "Harðar".into(),
+        "Björn" => return "Bjarna".into(),
+        _ => (),
+    }
+
+    if matronymic {
+        if let Some(stem) = parent.strip_suffix("ur") {
+            format!("{}ar", stem)
+        } else if let Some(stem) = parent.strip_suffix('a') {
+            format!("{}u", stem)
+        } else if parent.ends_with("ey") {
+            format!("{}jar", parent)
+        } else {
+            format!("{}ar", parent)
+        }
+    } else if let Some(stem) = parent.strip_suffix("ður") {
+        // Sigurður, Þórður: the -ar genitive of the strong -ður names.
+        format!("{}ðar", stem)
+    } else if let Some(stem) = parent.strip_suffix("ur") {
+        format!("{}s", stem)
+    } else if parent.ends_with('s') {
+        parent.into()
+    } else if let Some(stem) = parent.strip_suffix('i') {
+        format!("{}a", stem)
+    } else if let Some(stem) = parent.strip_suffix('a') {
+        format!("{}u", stem)
+    } else {
+        format!("{}s", parent)
+    }
+}
+
+fn icelandic(parent: &str, form: Form, matronymic: bool) -> String {
+    let suffix = match form {
+        Form::Masculine => "son",
+        Form::Feminine => "dóttir",
+        Form::Neutral => "bur",
+    };
+    format!("{}{}", icelandic_genitive(parent, matronymic), suffix)
+}
+
+fn arabic(father: &str, form: Form) -> String {
+    match form {
+        Form::Feminine => format!("bint {}", father),
+        _ => format!("ibn {}", father),
+    }
+}
+
+fn hebrew(father: &str, form: Form) -> String {
+    match form {
+        Form::Feminine => format!("bat {}", father),
+        _ => format!("ben {}", father),
+    }
+}
+
+/// Derive the patronymic (or matronymic) of someone of the given gender
+/// from their parent's given name.
+pub(crate) fn derive(
+    style: PatronymicStyle,
+    parent_name: String,
+    gender: Gender,
+    matronymic: bool,
+    rng: &mut impl Rng,
+) -> Patronymic {
+    let form = form(style, gender, rng);
+    let text = match style {
+        PatronymicStyle::Russian => russian(&parent_name, form),
+        PatronymicStyle::Ukrainian => ukrainian(&parent_name, form),
+        PatronymicStyle::Icelandic => icelandic(&parent_name, form, matronymic),
+        PatronymicStyle::Arabic => arabic(&parent_name, form),
+        PatronymicStyle::Hebrew => hebrew(&parent_name, form),
+    };
+
+    Patronymic {
+        parent_name,
+        text,
+        matronymic,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check a style's patronymics against known father → son, daughter
+    /// pairs.
+    fn check(derive: fn(&str, Form) -> String, cases: &[(&str, &str, &str)]) {
+        for &(father, son, daughter) in cases {
+            assert_eq!(derive(father, Form::Masculine), son, "son of {}", father);
+            assert_eq!(
+                derive(father, Form::Feminine),
+                daughter,
+                "daughter of {}",
+                father
+            );
+        }
+    }
+
+    #[test]
+    fn russian_patronymics() {
+        check(
+            russian,
+            &[
+                ("Ivan", "Ivanovich", "Ivanovna"),
+                ("Igor", "Igorevich", "Igorevna"),
+                ("Igor'", "Igorevich", "Igorevna"),
+                ("Nikolai", "Nikolaevich", "Nikolaevna"),
+                ("Sergei", "Sergeevich", "Sergeevna"),
+                ("Dmitriy", "Dmitrievich", "Dmitrievna"),
+                ("Nikita", "Nikitich", "Nikitichna"),
+                ("Ilya", "Ilyich", "Ilyinichna"),
+                ("Foma", "Fomich", "Fominichna"),
+                ("Pavel", "Pavlovich", "Pavlovna"),
+                ("Lev", "Lvovich", "Lvovna"),
+                ("Pyotr", "Petrovich", "Petrovna"),
+                ("Yakov", "Yakovlevich", "Yakovlevna"),
+            ],
+        );
+    }
+
+    #[test]
+    fn ukrainian_patronymics() {
+        check(
+            ukrainian,
+            &[
+                ("Ivan", "Ivanovych", "Ivanivna"),
+                ("Mykola", "Mykolayovych", "Mykolayivna"),
+                ("Petro", "Petrovych", "Petrivna"),
+                ("Illia", "Illich", "Illivna"),
+                ("Andriy", "Andriyovych", "Andriyivna"),
+            ],
+        );
+    }
+
+    #[test]
+    fn icelandic_patronymics() {
+        let patronymic = |father: &str, form| icelandic(father, form, false);
+        check(
+            patronymic,
+            &[
+                ("Jón", "Jónsson", "Jónsdóttir"),
+                ("Sigurður", "Sigurðarson", "Sigurðardóttir"),
+                ("Þórður", "Þórðarson", "Þórðardóttir"),
+                ("Hörður", "Harðarson", "Harðardóttir"),
+                ("Guðmundur", "Guðmundsson", "Guðmundsdóttir"),
+                ("Einar", "Einarsson", "Einarsdóttir"),
+                ("Magnús", "Magnússon", "Magnúsdóttir"),
+                ("Ari", "Arason", "Aradóttir"),
+                ("Sturla", "Sturluson", "Sturludóttir"),
+                ("Björn", "Bjarnason", "Bjarnadóttir"),
+            ],
+        );
+        assert_eq!(icelandic("Jón", Form::Neutral, false), "Jónsbur");
+    }
+
+    #[test]
+    fn icelandic_matronymics() {
+        let matronymic = |mother: &str, form| icelandic(mother, form, true);
+        check(
+            matronymic,
+            &[
+                ("Guðrún", "Guðrúnarson", "Guðrúnardóttir"),
+                ("Helga", "Helguson", "Helgudóttir"),
+                ("Bryndís", "Bryndísarson", "Bryndísardóttir"),
+            ],
+        );
+    }
+
+    #[test]
+    fn arabic_and_hebrew_patronymics() {
+        check(arabic, &[("Ahmad", "ibn Ahmad", "bint Ahmad")]);
+        check(hebrew, &[("David", "ben David", "bat David")]);
+    }
+}
@@ -0,0 +1,235 @@
+use behindthename::types::Gender;
+
+use crate::resources::usages::{gendered_surname_style, GenderedSurnameStyle};
+
+/// Masculine → feminine surname endings, most specific first.
+fn feminine_endings(style: GenderedSurnameStyle) -> &'static [(&'static str, &'static str)] {
+    match style {
+        GenderedSurnameStyle::EastSlavic => &[
+            ("skiy", "skaya"),
+            ("skii", "skaya"),
+            ("skoy", "skaya"),
+            ("sky", "skaya"),
+            ("oy", "aya"),
+            ("iy", "aya"),
+            ("ov", "ova"),
+            ("ev", "eva"),
+            ("in", "ina"),
+            ("yn", "yna"),
+        ],
+        GenderedSurnameStyle::Ukrainian => &[
+            ("skyi", "ska"),
+            ("skyy", "ska"),
+            ("skiy", "ska"),
+            ("sky", "ska"),
+            ("ov", "ova"),
+            ("ev", "eva"),
+            ("in", "ina"),
+            ("yn", "yna"),
+        ],
+        GenderedSurnameStyle::SouthSlavic => {
+            &[("ski", "ska"), ("ov", "ova"), ("ev", "eva"), ("in", "ina")]
+        }
+        GenderedSurnameStyle::Polish => &[("ski", "ska"), ("cki", "cka"), ("dzki", "dzka")],
+        GenderedSurnameStyle::Czech => &[("ský", "ská"), ("cký", "cká"), ("ý", "á"), ("a", "ová")],
+        GenderedSurnameStyle::Greek => &[("os", "ou"), ("as", "a"), ("is", "i")],
+    }
+}
+
+/// Feminine → masculine surname endings, for surnames given in their
+/// feminine form. Only endings that can be told apart are listed.
+fn masculine_endings(style: GenderedSurnameStyle) -> &'static [(&'static str, &'static str)] {
+    match style {
+        GenderedSurnameStyle::EastSlavic => &[
+            ("skaya", "sky"),
+            ("ova", "ov"),
+            ("eva", "ev"),
+            ("ina", "in"),
+            ("yna", "yn"),
+        ],
+        GenderedSurnameStyle::Ukrainian => &[
+            ("ska", "skyi"),
+            ("ova", "ov"),
+            ("eva", "ev"),
+            ("ina", "in"),
+            ("yna", "yn"),
+        ],
+        GenderedSurnameStyle::SouthSlavic => {
+            &[("ska", "ski"), ("ova", "ov"), ("eva", "ev"), ("ina", "in")]
+        }
+        GenderedSurnameStyle::Polish => &[("ska", "ski"), ("cka", "cki"), ("dzka", "dzki")],
+        GenderedSurnameStyle::Czech => &[("ská", "ský"), ("cká", "cký"), ("ová", ""), ("á", "ý")],
+        GenderedSurnameStyle::Greek => &[("ou", "os"), ("a", "as"), ("i", "is")],
+    }
+}
+
+fn swap_ending(surname: &str, endings: &[(&str, &str)]) -> Option<String> {
+    endings.iter().find_map(|(from, to)| {
+        surname
+            .strip_suffix(from)
+            .filter(|stem| !stem.is_empty())
+            .map(|stem| format!("{}{}", stem, to))
+    })
+}
+
+fn ends_with_consonant(surname: &str) -> bool {
+    surname
+        .chars()
+        .last()
+        .is_some_and(|c| c.is_alphabetic() && !"aáeéěiíoóuúůyý".contains(c))
+}
+
+fn masculine(style: GenderedSurnameStyle, surname: &str) -> String {
+    swap_ending(surname, masculine_endings(style)).unwrap_or_else(|| surname.into())
+}
+
+fn feminine(style: GenderedSurnameStyle, surname: &str) -> String {
+    let masculine = masculine(style, surname);
+    match swap_ending(&masculine, feminine_endings(style)) {
+        Some(feminine) => feminine,
+        // Czech and Slovak add -ová to surnames ending in a consonant,
+        // e.g. Novák, Nováková.
+        None if style == GenderedSurnameStyle::Czech && ends_with_consonant(&masculine) => {
+            format!("{}ová", masculine)
+        }
+        None => masculine,
+    }
+}
+
+/// The form of a surname a person of the given gender carries, for cultures
+/// whose surnames agree with gender, e.g. Ivanov, Ivanova. The surname may
+/// be given in either form; it's left alone for other genders.
+pub(crate) fn gendered(style: GenderedSurnameStyle, surname: &str, gender: Gender) -> String {
+    match gender {
+        Gender::Male => masculine(style, surname),
+        Gender::Female => feminine(style, surname),
+        _ => surname.into(),
+    }
+}
+
+/// A surname in the form a person of the given gender carries in a usage,
+/// unchanged for usages whose surnames don't agree with gender.
+pub(crate) fn gendered_for(usage_code: &str, surname: &str, gender: Gender) -> String {
+    match gendered_surname_style(usage_code) {
+        Some(style) => gendered(style, surname, gender),
+        None => surname.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check a style's surnames against known masculine, feminine pairs,
+    /// deriving each form from both.
+    fn check(style: GenderedSurnameStyle, cases: &[(&str, &str)]) {
+        for &(man, woman) in cases {
+            for given in [man, woman] {
+                assert_eq!(gendered(style, given, Gender::Male), man, "man {}", given);
+                assert_eq!(
+                    gendered(style, given, Gender::Female),
+                    woman,
+                    "woman {}",
+                    given
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn east_slavic_surnames() {
+        check(
+            GenderedSurnameStyle::EastSlavic,
+            &[
+                ("Ivanov", "Ivanova"),
+                ("Lebedev", "Lebedeva"),
+                ("Pushkin", "Pushkina"),
+                ("Borodin", "Borodina"),
+                ("Dostoevsky", "Dostoevskaya"),
+                ("Tsvetkov", "Tsvetkova"),
+            ],
+        );
+        let style = GenderedSurnameStyle::EastSlavic;
+        assert_eq!(gendered(style, "Tolstoy", Gender::Female), "Tolstaya");
+        assert_eq!(
+            gendered(style, "Zhitomirskiy", Gender::Female),
+            "Zhitomirskaya"
+        );
+    }
+
+    #[test]
+    fn ukrainian_surnames() {
+        check(
+            GenderedSurnameStyle::Ukrainian,
+            &[
+                ("Kovalenko", "Kovalenko"),
+                ("Shevchuk", "Shevchuk"),
+                ("Franko", "Franko"),
+                ("Ivanov", "Ivanova"),
+                ("Hrushevskyi", "Hrushevska"),
+            ],
+        );
+    }
+
+    #[test]
+    fn south_slavic_surnames() {
+        check(
+            GenderedSurnameStyle::SouthSlavic,
+            &[
+                ("Dimitrov", "Dimitrova"),
+                ("Georgiev", "Georgieva"),
+                ("Petrovski", "Petrovska"),
+            ],
+        );
+    }
+
+    #[test]
+    fn polish_surnames() {
+        check(
+            GenderedSurnameStyle::Polish,
+            &[
+                ("Kowalski", "Kowalska"),
+                ("Wiśniewski", "Wiśniewska"),
+                ("Zawadzki", "Zawadzka"),
+                ("Nowak", "Nowak"),
+                ("Lewandowski", "Lewandowska"),
+            ],
+        );
+    }
+
+    #[test]
+    fn czech_surnames() {
+        check(
+            GenderedSurnameStyle::Czech,
+            &[
+                ("Novák", "Nováková"),
+                ("Dvořák", "Dvořáková"),
+                ("Černý", "Černá"),
+                ("Novotný", "Novotná"),
+            ],
+        );
+        // Surnames in -a lose it in the feminine form, which can't be told
+        // apart from a consonant stem on the way back.
+        let style = GenderedSurnameStyle::Czech;
+        assert_eq!(gendered(style, "Procházka", Gender::Female), "Procházková");
+    }
+
+    #[test]
+    fn greek_surnames() {
+        check(
+            GenderedSurnameStyle::Greek,
+            &[
+                ("Papadopoulos", "Papadopoulou"),
+                ("Papadakis", "Papadaki"),
+                ("Kalogeras", "Kalogera"),
+            ],
+        );
+    }
+
+    #[test]
+    fn keeps_surnames_for_other_genders() {
+        let style = GenderedSurnameStyle::EastSlavic;
+        assert_eq!(gendered(style, "Ivanova", Gender::Any), "Ivanova");
+        assert_eq!(gendered(style, "Ivanov", Gender::Neutral), "Ivanov");
+    }
+}
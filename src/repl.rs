@@ -0,0 +1,346 @@
+//! A REPL running the bot's slash commands without Discord, for trying
+//! changes to /name and /about_name locally.
+
+use lazy_static::lazy_static;
+use poise::serenity_prelude::{GuildId, UserId};
+use regex::Regex;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use crate::commands::names::MessageContent;
+use crate::commands::settings::stored_settings;
+use crate::dispatch::{self, Invocation, COMMANDS};
+use crate::resources::types::Backend;
+use crate::storage::Storage;
+
+const PROMPT: &str = "rnc> ";
+const HELP: &str = "\
+Commands run as if sent in Discord, e.g.:
+  /name gender:f mode:medieval
+  /name heritage:\"iri:3 ita:1\" seed:42
+  /about_name name:\"Ada Lovelace\"
+Quote values with spaces. /name apply:true sets the simulated nickname
+in a server. Names come from the bot's own lists, offline, unless a
+backend is passed or set. The session is changed with:
+  :backend <b>      where names come from: local, or btn for BehindTheName
+  :nick [nickname]  set the nickname /about_name looks up, or clear it
+  :user <id>        use another user's preferences
+  :guild [id]       use a server's settings, or none as in DMs
+  :whoami           show the simulated user
+  :help             show this help
+  :quit             leave (or Ctrl-D)";
+
+lazy_static! {
+    /// A Markdown link, as in embed fields.
+    static ref LINK: Regex = Regex::new(r"\[([^\]]*)\]\(([^)]*)\)").unwrap();
+}
+
+/// Who the commands are run as, and where /name's names come from.
+struct Session {
+    user_id: UserId,
+    guild_id: Option<GuildId>,
+    nickname: Option<String>,
+    backend: Backend,
+}
+
+/// Split a line into words, keeping double-quoted text together, e.g.
+/// `heritage:"iri:3 ita:1"` is one word.
+fn words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !quoted => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        return Err("Unclosed quote".into());
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Parse `/command option:value...` as it would arrive from Discord.
+fn invocation(session: &Session, line: &str) -> Result<Invocation, String> {
+    let words = words(line)?;
+    let Some((command, options)) = words.split_first() else {
+        return Err("No command given".into());
+    };
+    let command = command.trim_start_matches('/');
+    if !COMMANDS.contains(&command) {
+        return Err(format!(
+            "Unknown command /{}; try {}",
+            command,
+            COMMANDS.map(|command| format!("/{}", command)).join(" or ")
+        ));
+    }
+    let mut options: Vec<(String, String)> = options
+        .iter()
+        .map(|option| match option.split_once(':') {
+            Some((name, value)) => Ok((name.to_string(), value.to_string())),
+            None => Err(format!("Expected option:value, got {}", option)),
+        })
+        .collect::<Result<_, _>>()?;
+    if command == "name" && !options.iter().any(|(name, _)| name == "backend") {
+        options.push(("backend".into(), session.backend.to_string()));
+    }
+
+    Ok(Invocation {
+        command: command.into(),
+        options,
+        user_id: session.user_id,
+        guild_id: session.guild_id,
+        nickname: session.nickname.clone(),
+        simulated: true,
+    })
+}
+
+/// A message as text, with links shown as their text and URL.
+fn render(message: &MessageContent) -> String {
+    let mut lines = vec![message.content.clone()];
+    if let Some(embed) = &message.embed {
+        lines.push(String::new());
+        lines.push(format!("[{}]", embed.title));
+        for (name, value, _) in &embed.fields {
+            lines.push(format!("{}: {}", name, LINK.replace_all(value, "$1 <$2>")));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Change the simulated user or the backend, returning what to show.
+fn set(session: &mut Session, command: &str, argument: Option<&str>) -> Result<String, String> {
+    let parse_id = |argument: Option<&str>| -> Result<u64, String> {
+        argument
+            .ok_or("An ID is needed")?
+            .parse()
+            .map_err(|e| format!("Not an ID: {}", e))
+    };
+    match command {
+        "nick" => session.nickname = argument.map(String::from),
+        "user" => session.user_id = UserId(parse_id(argument)?),
+        "guild" => {
+            session.guild_id = argument
+                .map(|id| parse_id(Some(id)).map(GuildId))
+                .transpose()?
+        }
+        "backend" => {
+            session.backend = argument
+                .ok_or("A backend is needed: local or btn")?
+                .parse()
+                .map_err(|e| format!("Not a backend: {}", e))?
+        }
+        "whoami" => {}
+        "help" => return Ok(HELP.into()),
+        other => return Err(format!("Unknown command :{}; try :help", other)),
+    }
+    Ok(format!(
+        "User {}, {}, nickname {}, names from {}",
+        session.user_id,
+        session.guild_id.map_or("in DMs".into(), |guild_id| format!(
+            "in server {}",
+            guild_id
+        )),
+        session.nickname.as_deref().unwrap_or("none"),
+        session.backend
+    ))
+}
+
+/// Read commands until the user quits. Settings and preferences are read
+/// from `storage`, which may be a copy of the bot's database.
+pub(crate) async fn run(storage: Storage) -> Result<(), ReadlineError> {
+    let mut editor = DefaultEditor::new()?;
+    let mut session = Session {
+        user_id: UserId(1),
+        guild_id: None,
+        nickname: None,
+        backend: Backend::Local,
+    };
+    println!("Type :help for help.");
+
+    loop {
+        let line = match tokio::task::block_in_place(|| editor.readline(PROMPT)) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line)?;
+
+        if let Some(command) = line.strip_prefix(':') {
+            let (command, argument) = match command.split_once(char::is_whitespace) {
+                Some((command, argument)) => (command, Some(argument.trim())),
+                None => (command, None),
+            };
+            if command == "quit" {
+                return Ok(());
+            }
+            match set(&mut session, command, argument) {
+                Ok(text) => println!("{}", text),
+                Err(e) => eprintln!("{}", e),
+            }
+            continue;
+        }
+
+        match invocation(&session, line) {
+            Ok(invocation) => {
+                let settings = stored_settings(&storage, invocation.guild_id);
+                let answer = dispatch::run(&storage, &invocation, settings).await;
+                println!("{}", render(&answer.message));
+                if let Some(nickname) = answer.nickname {
+                    println!("\nChanged your nickname to {}.", nickname);
+                    session.nickname = Some(nickname);
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::names::MessageEmbed;
+
+    use super::*;
+
+    fn session() -> Session {
+        Session {
+            user_id: UserId(7),
+            guild_id: Some(GuildId(8)),
+            nickname: Some("Ada".into()),
+            backend: Backend::Local,
+        }
+    }
+
+    #[test]
+    fn splits_words() {
+        assert_eq!(
+            words("  /name  gender:f\tseed:42 ").unwrap(),
+            ["/name", "gender:f", "seed:42"]
+        );
+        assert_eq!(words("").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn keeps_quoted_text_together() {
+        assert_eq!(
+            words(r#"/name heritage:"iri:3 ita:1" exclude:"" x"y z""#).unwrap(),
+            ["/name", "heritage:iri:3 ita:1", "exclude:", "xy z"]
+        );
+        assert_eq!(
+            words(r#"/about_name name:"Ada"#),
+            Err("Unclosed quote".into())
+        );
+    }
+
+    #[test]
+    fn parses_invocations() {
+        let invocation =
+            invocation(&session(), r#"name mode:medieval heritage:"iri:3 ita""#).unwrap();
+        assert_eq!(invocation.command, "name");
+        assert_eq!(
+            invocation.options,
+            [
+                ("mode".into(), "medieval".into()),
+                ("heritage".into(), "iri:3 ita".into()),
+                ("backend".into(), "local".into())
+            ]
+        );
+        assert_eq!(invocation.user_id, UserId(7));
+        assert_eq!(invocation.guild_id, Some(GuildId(8)));
+        assert_eq!(invocation.nickname.as_deref(), Some("Ada"));
+        assert!(invocation.simulated);
+
+        let about_name = super::invocation(&session(), "/about_name").unwrap();
+        assert_eq!(about_name.command, "about_name");
+        assert!(about_name.options.is_empty());
+    }
+
+    #[test]
+    fn passes_the_session_backend() {
+        let mut session = session();
+        let backend = |session: &Session, line| {
+            let invocation = invocation(session, line).unwrap();
+            let option = invocation
+                .options
+                .into_iter()
+                .find(|(name, _)| name == "backend");
+            option.map(|(_, value)| value)
+        };
+        assert_eq!(backend(&session, "/name").as_deref(), Some("local"));
+        assert_eq!(
+            backend(&session, "/name backend:btn").as_deref(),
+            Some("btn")
+        );
+        assert_eq!(backend(&session, "/about_name"), None);
+
+        set(&mut session, "backend", Some("btn")).unwrap();
+        assert_eq!(backend(&session, "/name").as_deref(), Some("btn"));
+        assert!(set(&mut session, "backend", Some("elsewhere")).is_err());
+        assert!(set(&mut session, "backend", None).is_err());
+    }
+
+    #[tokio::test]
+    async fn names_offline() {
+        crate::config::install_test_config();
+        let storage = Storage::open(":memory:").unwrap();
+        let invocation = invocation(&session(), "/name gender:f seed:42").unwrap();
+        let settings = stored_settings(&storage, invocation.guild_id);
+        let answer = dispatch::run(&storage, &invocation, settings).await;
+        let text = render(&answer.message);
+        assert!(text.contains("[BehindTheName]"), "{}", text);
+        assert!(text.ends_with("Seed: 42"), "{}", text);
+    }
+
+    #[test]
+    fn rejects_invalid_invocations() {
+        let error = |line| invocation(&session(), line).err().unwrap();
+        assert_eq!(error(""), "No command given");
+        assert_eq!(
+            error("/family"),
+            "Unknown command /family; try /name or /about_name"
+        );
+        assert_eq!(error("/name gender"), "Expected option:value, got gender");
+        assert_eq!(error(r#"/name gender:"f"#), "Unclosed quote");
+    }
+
+    #[test]
+    fn renders_messages() {
+        let text = MessageContent {
+            content: "Nicknames can only be applied in a server.".into(),
+            embed: None,
+        };
+        assert_eq!(render(&text), "Nicknames can only be applied in a server.");
+
+        let name = MessageContent {
+            content: "**Ada Lovelace**".into(),
+            embed: Some(MessageEmbed {
+                title: "BehindTheName".into(),
+                fields: vec![
+                    (
+                        "First Name".into(),
+                        "[Ada](https://www.behindthename.com/name/ada)".into(),
+                        true,
+                    ),
+                    ("Usage".into(), "English".into(), true),
+                ],
+            }),
+        };
+        assert_eq!(
+            render(&name),
+            "**Ada Lovelace**\n\n[BehindTheName]\n\
+             First Name: Ada <https://www.behindthename.com/name/ada>\n\
+             Usage: English"
+        );
+    }
+}
@@ -1,6 +1,11 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::sync::OnceLock;
+
 use aho_corasick::AhoCorasick;
+use behindthename::types::Usage;
 use lazy_static::lazy_static;
-use regex::{Regex, RegexSet};
+use regex::Regex;
 use serde::Deserialize;
 use serde_json::from_str;
 
@@ -11,19 +16,12 @@ struct NormPair {
 }
 
 const LUT: &str = include_str!("lut.json");
+const USAGE_RULES: &str = include_str!("usage_rules.json");
+
+static INSTALLED_RULES: OnceLock<UsageRules> = OnceLock::new();
 
 lazy_static! {
-    pub(crate) static ref USAGE_MAP: [(Regex, &'static str); 2] = [
-        (Regex::new("^gmc-.+$").unwrap(), "ger"),
-        (Regex::new("^(.+)-(?:myth|bibl|medi)$").unwrap(), "$1"),
-    ];
-    pub(crate) static ref USAGE_REGEX: RegexSet = RegexSet::new(
-        USAGE_MAP
-            .iter()
-            .map(|(pat, _)| pat.as_str())
-            .collect::<Vec<_>>()
-    )
-    .unwrap();
+    static ref REPLACEMENT_GROUP: Regex = Regex::new(r"\$(?:\{([^}]*)\}|([0-9A-Za-z_]+))").unwrap();
     static ref NMAP_JSON: Vec<NormPair> = from_str(LUT).unwrap();
     pub(crate) static ref NORM_TARGETS: Vec<&'static str> = NMAP_JSON
         .iter()
@@ -35,3 +33,245 @@ lazy_static! {
         .collect();
     pub(crate) static ref NORM_AC: AhoCorasick = AhoCorasick::new(NORM_TARGETS.iter());
 }
+
+/// What to do with a usage that a rule rewrites.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RuleAction {
+    /// Also try the rewritten usage, after every usage given.
+    Append,
+    /// Try the rewritten usage in place of the one given.
+    Substitute,
+}
+
+/// A usage rewrite rule as written in a rules file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleSpec {
+    pattern: String,
+    replacement: String,
+    #[serde(default)]
+    priority: i32,
+    action: RuleAction,
+}
+
+/// A rule rewriting usage codes BehindTheName has few surnames for into
+/// related ones it has more for, e.g. "eng-medi" to "eng".
+pub(crate) struct UsageRule {
+    pub(crate) pattern: Regex,
+    /// With `$1` or `${name}` for the pattern's groups.
+    pub(crate) replacement: String,
+    pub(crate) priority: i32,
+    pub(crate) action: RuleAction,
+}
+
+/// Usage rewrite rules, from the highest to the lowest priority. Rules of
+/// the same priority keep their order in the file.
+#[derive(Default)]
+pub(crate) struct UsageRules {
+    pub(crate) rules: Vec<UsageRule>,
+}
+
+/// Check that a replacement only refers to groups its pattern has.
+fn check_replacement(pattern: &Regex, replacement: &str) -> Result<(), String> {
+    if replacement.trim().is_empty() {
+        return Err("empty replacement".into());
+    }
+    // "$$" is a literal "$", not the start of a group.
+    let unescaped = replacement.replace("$$", "");
+    for group in REPLACEMENT_GROUP.captures_iter(&unescaped) {
+        let group = group.get(1).or_else(|| group.get(2)).unwrap().as_str();
+        let exists = match group.parse::<usize>() {
+            Ok(index) => index < pattern.captures_len(),
+            Err(_) => pattern.capture_names().flatten().any(|name| name == group),
+        };
+        if !exists {
+            return Err(format!("replacement refers to a missing group ${}", group));
+        }
+    }
+    Ok(())
+}
+
+impl UsageRules {
+    /// Parse and check rules written as JSON, naming the first rule that's
+    /// wrong.
+    pub(crate) fn parse(json: &str) -> Result<UsageRules, String> {
+        let specs: Vec<RuleSpec> = from_str(json).map_err(|e| e.to_string())?;
+        let mut rules = specs
+            .into_iter()
+            .enumerate()
+            .map(|(index, spec)| {
+                let pattern =
+                    Regex::new(&spec.pattern).map_err(|e| format!("rule {}: {}", index + 1, e))?;
+                check_replacement(&pattern, &spec.replacement)
+                    .map_err(|e| format!("rule {}: {}", index + 1, e))?;
+                Ok(UsageRule {
+                    pattern,
+                    replacement: spec.replacement,
+                    priority: spec.priority,
+                    action: spec.action,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        rules.sort_by_key(|rule| Reverse(rule.priority));
+        Ok(UsageRules { rules })
+    }
+
+    pub(crate) fn read(path: &str) -> Result<UsageRules, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("At reading {}: {}", path, e))?;
+        UsageRules::parse(&json).map_err(|e| format!("At parsing {}: {}", path, e))
+    }
+
+    /// The rules bundled with the bot.
+    pub(crate) fn bundled() -> UsageRules {
+        UsageRules::parse(USAGE_RULES).unwrap()
+    }
+
+    /// Make these the rules returned by `usage_rules()`.
+    pub(crate) fn install(self) {
+        if INSTALLED_RULES.set(self).is_err() {
+            panic!("Usage rules were already installed");
+        }
+    }
+
+    /// The rule for a usage code: the first matching one.
+    pub(crate) fn rule_for(&self, usage_code: &str) -> Option<&UsageRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.pattern.is_match(usage_code))
+    }
+
+    /// Rewrite a usage code with its rule, e.g. "eng-medi" to "eng".
+    pub(crate) fn rewrite(&self, usage_code: &str) -> Option<String> {
+        self.rule_for(usage_code).map(|rule| {
+            rule.pattern
+                .replace(usage_code, rule.replacement.as_str())
+                .into_owned()
+        })
+    }
+
+    /// The usages to try for a surname, in order: those given, with the
+    /// ones a rule applies to substituted, then the appended ones.
+    pub(crate) fn augment(&self, usages: Vec<Usage>) -> Vec<Usage> {
+        let mut appended = vec![];
+        let mut augmented = usages
+            .into_iter()
+            .map(|usage| {
+                let Some(rule) = self.rule_for(&usage.usage_code) else {
+                    return usage;
+                };
+                let rewritten = Usage {
+                    usage_code: self.rewrite(&usage.usage_code).unwrap(),
+                    ..usage.clone()
+                };
+                match rule.action {
+                    RuleAction::Append => {
+                        appended.push(rewritten);
+                        usage
+                    }
+                    RuleAction::Substitute => rewritten,
+                }
+            })
+            .collect::<Vec<_>>();
+        augmented.append(&mut appended);
+        augmented
+    }
+}
+
+/// The installed usage rules, or the bundled ones if none were installed.
+pub(crate) fn usage_rules() -> &'static UsageRules {
+    INSTALLED_RULES.get_or_init(UsageRules::bundled)
+}
+
+#[cfg(test)]
+mod tests {
+    use behindthename::types::Gender;
+
+    use super::*;
+
+    fn usages(codes: &[&str]) -> Vec<Usage> {
+        codes
+            .iter()
+            .map(|code| Usage {
+                usage_code: code.to_string(),
+                usage_full: code.to_string(),
+                usage_gender: Gender::Any,
+            })
+            .collect()
+    }
+
+    fn codes(usages: &[Usage]) -> Vec<&str> {
+        usages
+            .iter()
+            .map(|usage| usage.usage_code.as_str())
+            .collect()
+    }
+
+    fn parse_error(json: &str) -> String {
+        match UsageRules::parse(json) {
+            Ok(_) => panic!("{} parsed", json),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn bundled_rules_append_after_every_usage() {
+        let augmented = UsageRules::bundled().augment(usages(&["eng-medi", "gmc-old", "iri"]));
+        assert_eq!(
+            codes(&augmented),
+            ["eng-medi", "gmc-old", "iri", "eng", "ger"]
+        );
+    }
+
+    #[test]
+    fn higher_priority_rules_apply_first() {
+        let rules = UsageRules::parse(
+            r#"[
+                {"pattern": "^eng-.+$", "replacement": "eng", "action": "substitute"},
+                {"pattern": "^eng-medi$", "replacement": "sco", "priority": 2, "action": "substitute"},
+                {"pattern": "^eng-mod$", "replacement": "usa", "action": "substitute"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(rules.rewrite("eng-medi").as_deref(), Some("sco"));
+        // Of rules with the same priority, the first in the file applies.
+        assert_eq!(rules.rewrite("eng-mod").as_deref(), Some("eng"));
+        assert_eq!(rules.rewrite("iri"), None);
+    }
+
+    #[test]
+    fn substitute_rules_replace_in_place() {
+        let rules = UsageRules::parse(
+            r#"[{"pattern": "^(?P<base>.+)-medi$", "replacement": "${base}", "action": "substitute"}]"#,
+        )
+        .unwrap();
+        let augmented = rules.augment(usages(&["eng-medi", "iri", "fre-medi"]));
+        assert_eq!(codes(&augmented), ["eng", "iri", "fre"]);
+    }
+
+    #[test]
+    fn rejects_missing_groups() {
+        let error = parse_error(
+            r#"[
+                {"pattern": "^eng-medi$", "replacement": "eng", "action": "append"},
+                {"pattern": "^(.+)-medi$", "replacement": "$2", "action": "append"}
+            ]"#,
+        );
+        assert_eq!(error, "rule 2: replacement refers to a missing group $2");
+        let error = parse_error(
+            r#"[{"pattern": "^(?P<base>.+)-medi$", "replacement": "${stem}", "action": "append"}]"#,
+        );
+        assert_eq!(error, "rule 1: replacement refers to a missing group $stem");
+        assert!(UsageRules::parse(
+            r#"[{"pattern": "^eng$", "replacement": "$$1", "action": "append"}]"#
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        let error =
+            parse_error(r#"[{"pattern": "^(eng", "replacement": "eng", "action": "append"}]"#);
+        assert!(error.starts_with("rule 1: regex parse error"), "{}", error);
+    }
+}
@@ -1,2 +1,4 @@
 pub(crate) mod maps;
+pub(crate) mod names;
 pub(crate) mod types;
+pub(crate) mod usages;
@@ -0,0 +1,31 @@
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde_json::from_str;
+
+/// Common names of a usage, bundled with the bot for generating names
+/// without BehindTheName.
+#[derive(Deserialize, Debug)]
+pub(crate) struct LocalNames {
+    pub(crate) usage: String,
+    pub(crate) masculine: Vec<String>,
+    pub(crate) feminine: Vec<String>,
+    /// Empty for usages whose names carry a patronymic instead.
+    pub(crate) surnames: Vec<String>,
+}
+
+const NAMES: &str = include_str!("names.json");
+
+lazy_static! {
+    pub(crate) static ref LOCAL_NAMES: Vec<LocalNames> = from_str(NAMES).unwrap();
+}
+
+pub(crate) fn local_names(usage_code: &str) -> Option<&'static LocalNames> {
+    LOCAL_NAMES.iter().find(|names| names.usage == usage_code)
+}
+
+pub(crate) fn local_usage_codes() -> Vec<&'static str> {
+    LOCAL_NAMES
+        .iter()
+        .map(|names| names.usage.as_str())
+        .collect()
+}
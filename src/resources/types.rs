@@ -1,9 +1,29 @@
+use std::fmt;
 use std::str::FromStr;
+use std::sync::Arc;
 
+use behindthename::types::{Gender, Usage};
+use rand::seq::SliceRandom;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-pub(crate) struct Data {} // User data, which is stored and accessible in all command invocations
+use crate::naming::constraints::Constraints;
+use crate::naming::filter::Exclusions;
+use crate::naming::patronymic::Patronymic;
+use crate::storage::Storage;
+
+pub(crate) struct Data {
+    pub(crate) storage: Arc<Storage>,
+} // User data, which is stored and accessible in all command invocations
+
+impl Data {
+    pub(crate) fn new(storage: Storage) -> Data {
+        Data {
+            storage: Arc::new(storage),
+        }
+    }
+}
 pub(crate) type Error = Box<dyn std::error::Error + Send + Sync>;
 pub(crate) type Context<'a> = poise::Context<'a, Data, Error>;
 
@@ -12,6 +32,10 @@ pub(crate) type Context<'a> = poise::Context<'a, Data, Error>;
 pub(crate) enum GenMode {
     Coherent,
     Chaotic,
+    Medieval,
+    Mythology,
+    Biblical,
+    Ancient,
 }
 
 impl FromStr for GenMode {
@@ -21,3 +45,219 @@ impl FromStr for GenMode {
         serde_json::from_value::<GenMode>(json!(s))
     }
 }
+
+impl fmt::Display for GenMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let val = serde_json::to_value(self).unwrap();
+        write!(f, "{}", val.as_str().unwrap())
+    }
+}
+
+/// Where names are drawn from: BehindTheName, or the bot's own name lists,
+/// which need no network and always give the same name for a seed.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Backend {
+    #[default]
+    Btn,
+    Local,
+}
+
+impl FromStr for Backend {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value::<Backend>(json!(s))
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let val = serde_json::to_value(self).unwrap();
+        write!(f, "{}", val.as_str().unwrap())
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExportFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+impl FromStr for ExportFormat {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value::<ExportFormat>(json!(s))
+    }
+}
+
+/// A list of genders given one letter per entry, e.g. "mfx"; x stands for
+/// any gender.
+pub(crate) struct GenderList(pub(crate) Vec<Gender>);
+
+impl FromStr for GenderList {
+    type Err = serde_json::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                'x' => Ok(Gender::Any),
+                c => Gender::from_str(&c.to_string()),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(GenderList)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ParseSpecError(pub(crate) String);
+
+impl fmt::Display for ParseSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseSpecError {}
+
+/// One or more usage codes with optional weights, e.g. "iri:3 ita".
+#[derive(Clone, Debug)]
+pub(crate) struct UsageSpec(pub(crate) Vec<(String, u32)>);
+
+impl UsageSpec {
+    pub(crate) fn choose(
+        &self,
+        exclusions: &Exclusions,
+        rng: &mut impl Rng,
+    ) -> Result<String, String> {
+        let allowed = self
+            .0
+            .iter()
+            .filter(|(code, _)| !exclusions.excludes(code))
+            .collect::<Vec<_>>();
+        allowed
+            .choose_weighted(rng, |(_, weight)| *weight)
+            .map(|(code, _)| code.clone())
+            .map_err(|_| format!("All of the usages {:?} are excluded", self.0))
+    }
+}
+
+impl fmt::Display for UsageSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items = self
+            .0
+            .iter()
+            .map(|(code, weight)| match weight {
+                1 => code.clone(),
+                weight => format!("{}:{}", code, weight),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "{}", items.join(" "))
+    }
+}
+
+impl FromStr for UsageSpec {
+    type Err = ParseSpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usages = s
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|item| !item.is_empty())
+            .map(|item| match item.split_once(':') {
+                Some((code, weight)) => match weight.parse::<u32>() {
+                    Ok(weight) if weight > 0 => Ok((code.to_lowercase(), weight)),
+                    _ => Err(ParseSpecError(format!("Invalid weight in {}", item))),
+                },
+                None => Ok((item.to_lowercase(), 1)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if usages.is_empty() {
+            Err(ParseSpecError("No usages given".into()))
+        } else {
+            Ok(UsageSpec(usages))
+        }
+    }
+}
+
+/// Options for generating a single name.
+#[derive(Clone, Default)]
+pub(crate) struct NameOptions {
+    pub(crate) gender: Option<Gender>,
+    pub(crate) mode: Option<GenMode>,
+    pub(crate) given_usage: Option<UsageSpec>,
+    pub(crate) surname_usage: Option<UsageSpec>,
+    pub(crate) exclusions: Exclusions,
+    pub(crate) constraints: Constraints,
+    pub(crate) seed: u64,
+    pub(crate) backend: Backend,
+}
+
+/// Defaults for the name options a command leaves unset, e.g. a guild's.
+#[derive(Clone, Default)]
+pub(crate) struct NameDefaults {
+    pub(crate) gender: Option<Gender>,
+    pub(crate) mode: Option<GenMode>,
+    /// Usages for both names, when neither usages nor a mode are given.
+    pub(crate) usages: Option<UsageSpec>,
+}
+
+impl NameDefaults {
+    /// These defaults, falling back to `fallback` for what they leave
+    /// unset. A mode and usages are one choice, so that e.g. a user's mode
+    /// isn't overridden by a guild's usages.
+    pub(crate) fn or(self, fallback: NameDefaults) -> NameDefaults {
+        let (mode, usages) = if self.mode.is_some() || self.usages.is_some() {
+            (self.mode, self.usages)
+        } else {
+            (fallback.mode, fallback.usages)
+        };
+        NameDefaults {
+            gender: self.gender.or(fallback.gender),
+            mode,
+            usages,
+        }
+    }
+}
+
+impl NameOptions {
+    /// Fill in the gender, mode and usages left unset from `defaults`.
+    /// Defaults don't override anything already set, so layers of defaults
+    /// are filled from the most to the least specific.
+    pub(crate) fn fill_defaults(&mut self, defaults: &NameDefaults) {
+        self.gender = self.gender.or(defaults.gender);
+        let usages_set = self.given_usage.is_some() || self.surname_usage.is_some();
+        if !usages_set && self.mode.is_none() {
+            self.given_usage.clone_from(&defaults.usages);
+            self.surname_usage.clone_from(&defaults.usages);
+        }
+        self.mode = self.mode.or(defaults.mode);
+    }
+
+    /// The mode the name will be generated in; "mixed" when usages are
+    /// given for the given name or surname.
+    pub(crate) fn mode_label(&self) -> String {
+        if self.given_usage.is_some() || self.surname_usage.is_some() {
+            "mixed".into()
+        } else {
+            self.mode.unwrap_or(GenMode::Coherent).to_string()
+        }
+    }
+}
+
+pub(crate) struct Name {
+    pub(crate) first_name: String,
+    pub(crate) patronymic: Option<Patronymic>,
+    pub(crate) last_name_result: Result<String, String>,
+    /// The maternal surname, for usages that carry two.
+    pub(crate) second_last_name: Option<String>,
+    /// A descriptive byname such as "the Bold", for eras without surnames.
+    pub(crate) epithet: Option<String>,
+    pub(crate) usage: Option<Usage>,
+    /// The usage of the given name, when it differs from that of the surname.
+    pub(crate) given_usage: Option<Usage>,
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use behindthename::types::{Gender, Usage};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::from_str;
+
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum NameOrder {
+    #[default]
+    GivenFirst,
+    FamilyFirst,
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PatronymicStyle {
+    Russian,
+    Ukrainian,
+    Icelandic,
+    Arabic,
+    Hebrew,
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DoubleSurnameStyle {
+    Spanish,
+    Catalan,
+    Portuguese,
+}
+
+impl DoubleSurnameStyle {
+    /// Whether the maternal surname comes before the paternal one.
+    pub(crate) fn maternal_first(self) -> bool {
+        self == DoubleSurnameStyle::Portuguese
+    }
+
+    /// The conjunction that may join the two surnames.
+    pub(crate) fn conjunction(self) -> &'static str {
+        match self {
+            DoubleSurnameStyle::Spanish => "y",
+            DoubleSurnameStyle::Catalan => "i",
+            DoubleSurnameStyle::Portuguese => "e",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum GenderedSurnameStyle {
+    EastSlavic,
+    SouthSlavic,
+    Ukrainian,
+    Polish,
+    Czech,
+    Greek,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Era {
+    Medieval,
+    Mythology,
+    Biblical,
+    Ancient,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct UsageInfo {
+    pub(crate) code: String,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) order: NameOrder,
+    #[serde(default)]
+    pub(crate) patronymic: Option<PatronymicStyle>,
+    #[serde(default)]
+    pub(crate) double_surname: Option<DoubleSurnameStyle>,
+    #[serde(default)]
+    pub(crate) gendered_surname: Option<GenderedSurnameStyle>,
+    #[serde(default)]
+    pub(crate) era: Option<Era>,
+}
+
+const USAGES: &str = include_str!("usages.json");
+
+lazy_static! {
+    static ref USAGE_LIST: Vec<UsageInfo> = from_str(USAGES).unwrap();
+    pub(crate) static ref USAGE_CATALOG: HashMap<&'static str, &'static UsageInfo> = USAGE_LIST
+        .iter()
+        .map(|info| (info.code.as_str(), info))
+        .collect();
+}
+
+pub(crate) fn usage_info(usage_code: &str) -> Option<&'static UsageInfo> {
+    USAGE_CATALOG.get(usage_code).copied()
+}
+
+/// Build a `Usage` for a usage code, named from the catalog where possible.
+pub(crate) fn usage_for(usage_code: &str, gender: Gender) -> Usage {
+    Usage {
+        usage_code: usage_code.into(),
+        usage_full: usage_info(usage_code)
+            .map_or_else(|| usage_code.into(), |info| info.name.clone()),
+        usage_gender: gender,
+    }
+}
+
+pub(crate) fn name_order(usage_code: &str) -> NameOrder {
+    usage_info(usage_code)
+        .map(|info| info.order)
+        .unwrap_or_default()
+}
+
+pub(crate) fn patronymic_style(usage_code: &str) -> Option<PatronymicStyle> {
+    usage_info(usage_code).and_then(|info| info.patronymic)
+}
+
+pub(crate) fn double_surname_style(usage_code: &str) -> Option<DoubleSurnameStyle> {
+    usage_info(usage_code).and_then(|info| info.double_surname)
+}
+
+pub(crate) fn gendered_surname_style(usage_code: &str) -> Option<GenderedSurnameStyle> {
+    usage_info(usage_code).and_then(|info| info.gendered_surname)
+}
+
+pub(crate) fn era_usages(era: Era) -> Vec<&'static UsageInfo> {
+    USAGE_LIST
+        .iter()
+        .filter(|info| info.era == Some(era))
+        .collect()
+}
+
+/// The era a usage belongs to, from the catalog or, for usages missing from
+/// it, from BehindTheName's code suffixes.
+pub(crate) fn era_of(usage_code: &str) -> Option<Era> {
+    match usage_info(usage_code) {
+        Some(info) => info.era,
+        None if usage_code.ends_with("-myth") => Some(Era::Mythology),
+        None if usage_code == "bibl" || usage_code.ends_with("-bibl") => Some(Era::Biblical),
+        None if usage_code.ends_with("-medi") => Some(Era::Medieval),
+        None if usage_code.ends_with("-anc") => Some(Era::Ancient),
+        None => None,
+    }
+}
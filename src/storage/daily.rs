@@ -0,0 +1,156 @@
+use poise::serenity_prelude::{ChannelId, GuildId};
+use rusqlite::{params, OptionalExtension, Row};
+
+use crate::storage::Storage;
+
+/// Where and when to post a guild's name of the day.
+pub(crate) struct DailyName {
+    pub(crate) guild_id: GuildId,
+    pub(crate) channel_id: ChannelId,
+    /// The local time to post at, as "HH:MM".
+    pub(crate) time: String,
+    /// An IANA time zone name, e.g. "Europe/Paris".
+    pub(crate) time_zone: String,
+    /// How many days a first name must wait before it's posted again.
+    pub(crate) no_repeat_days: u32,
+    /// The local date of the last post, as "YYYY-MM-DD".
+    pub(crate) last_posted: Option<String>,
+}
+
+fn daily_name(row: &Row<'_>) -> rusqlite::Result<DailyName> {
+    Ok(DailyName {
+        guild_id: GuildId(row.get::<_, i64>(0)? as u64),
+        channel_id: ChannelId(row.get::<_, i64>(1)? as u64),
+        time: row.get(2)?,
+        time_zone: row.get(3)?,
+        no_repeat_days: row.get(4)?,
+        last_posted: row.get(5)?,
+    })
+}
+
+const COLUMNS: &str = "guild_id, channel_id, time, time_zone, no_repeat_days, last_posted";
+
+impl Storage {
+    pub(crate) fn set_daily_name(&self, daily: &DailyName) -> rusqlite::Result<()> {
+        self.connection().execute(
+            "INSERT INTO daily_names (guild_id, channel_id, time, time_zone, no_repeat_days, last_posted)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (guild_id) DO UPDATE SET
+                channel_id = excluded.channel_id,
+                time = excluded.time,
+                time_zone = excluded.time_zone,
+                no_repeat_days = excluded.no_repeat_days,
+                last_posted = excluded.last_posted",
+            params![
+                daily.guild_id.0 as i64,
+                daily.channel_id.0 as i64,
+                daily.time,
+                daily.time_zone,
+                daily.no_repeat_days,
+                daily.last_posted
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn daily_name(&self, guild_id: GuildId) -> rusqlite::Result<Option<DailyName>> {
+        self.connection()
+            .query_row(
+                &format!("SELECT {} FROM daily_names WHERE guild_id = ?1", COLUMNS),
+                params![guild_id.0 as i64],
+                daily_name,
+            )
+            .optional()
+    }
+
+    pub(crate) fn daily_names(&self) -> rusqlite::Result<Vec<DailyName>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(&format!("SELECT {} FROM daily_names", COLUMNS))?;
+        let daily_names = statement.query_map([], daily_name)?.collect();
+        daily_names
+    }
+
+    pub(crate) fn remove_daily_name(&self, guild_id: GuildId) -> rusqlite::Result<bool> {
+        let removed = self.connection().execute(
+            "DELETE FROM daily_names WHERE guild_id = ?1",
+            params![guild_id.0 as i64],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub(crate) fn set_daily_posted(&self, guild_id: GuildId, date: &str) -> rusqlite::Result<()> {
+        self.connection().execute(
+            "UPDATE daily_names SET last_posted = ?2 WHERE guild_id = ?1",
+            params![guild_id.0 as i64, date],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn add_daily_post(
+        &self,
+        guild_id: GuildId,
+        first_name: &str,
+        full_name: &str,
+        posted_at: i64,
+    ) -> rusqlite::Result<()> {
+        self.connection().execute(
+            "INSERT INTO daily_name_posts (guild_id, first_name, full_name, posted_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![guild_id.0 as i64, first_name, full_name, posted_at],
+        )?;
+        Ok(())
+    }
+
+    /// The first names posted as a guild's name of the day since `since`,
+    /// in Unix seconds.
+    pub(crate) fn daily_first_names_since(
+        &self,
+        guild_id: GuildId,
+        since: i64,
+    ) -> rusqlite::Result<Vec<String>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT DISTINCT first_name FROM daily_name_posts
+             WHERE guild_id = ?1 AND posted_at >= ?2",
+        )?;
+        let first_names = statement
+            .query_map(params![guild_id.0 as i64, since], |row| row.get(0))?
+            .collect();
+        first_names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_first_names_posted_since() {
+        let storage = Storage::open(":memory:").unwrap();
+        let (guild, other_guild) = (GuildId(1), GuildId(2));
+        storage
+            .add_daily_post(guild, "Ada", "Ada Lovelace", 100)
+            .unwrap();
+        storage
+            .add_daily_post(guild, "Ada", "Ada Byron", 300)
+            .unwrap();
+        storage
+            .add_daily_post(guild, "Grace", "Grace Hopper", 200)
+            .unwrap();
+        storage
+            .add_daily_post(other_guild, "Alan", "Alan Turing", 300)
+            .unwrap();
+
+        let mut first_names = storage.daily_first_names_since(guild, 200).unwrap();
+        first_names.sort();
+        assert_eq!(first_names, ["Ada", "Grace"]);
+        assert_eq!(
+            storage.daily_first_names_since(guild, 250).unwrap(),
+            ["Ada"]
+        );
+        assert!(storage
+            .daily_first_names_since(guild, 301)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use poise::serenity_prelude::UserId;
+use rusqlite::types::Type;
+use rusqlite::{params, Row};
+
+use crate::naming::export::NameRecord;
+use crate::storage::Storage;
+
+/// A name a user saved, with its parts' BehindTheName links and usages.
+pub(crate) struct Favorite {
+    pub(crate) id: i64,
+    pub(crate) record: NameRecord,
+    pub(crate) created_at: i64,
+}
+
+fn favorite(row: &Row<'_>) -> rusqlite::Result<Favorite> {
+    let record: String = row.get(1)?;
+    Ok(Favorite {
+        id: row.get(0)?,
+        record: serde_json::from_str(&record)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, Type::Text, Box::new(e)))?,
+        created_at: row.get(2)?,
+    })
+}
+
+impl Storage {
+    /// Save a name for a user, returning false if they already saved it.
+    pub(crate) fn add_favorite(
+        &self,
+        user_id: UserId,
+        record: &NameRecord,
+        created_at: i64,
+    ) -> rusqlite::Result<bool> {
+        let record_json = serde_json::to_string(record)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let added = self.connection().execute(
+            "INSERT OR IGNORE INTO favorites (user_id, full_name, record, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![user_id.0 as i64, record.full_name, record_json, created_at],
+        )?;
+        Ok(added > 0)
+    }
+
+    /// A user's favorites, oldest first.
+    pub(crate) fn favorites(&self, user_id: UserId) -> rusqlite::Result<Vec<Favorite>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT id, record, created_at FROM favorites WHERE user_id = ?1 ORDER BY id",
+        )?;
+        let favorites = statement
+            .query_map(params![user_id.0 as i64], favorite)?
+            .collect();
+        favorites
+    }
+
+    pub(crate) fn favorite_count(&self, user_id: UserId) -> rusqlite::Result<usize> {
+        self.connection().query_row(
+            "SELECT COUNT(*) FROM favorites WHERE user_id = ?1",
+            params![user_id.0 as i64],
+            |row| row.get(0),
+        )
+    }
+
+    pub(crate) fn remove_favorite(&self, user_id: UserId, id: i64) -> rusqlite::Result<bool> {
+        let removed = self.connection().execute(
+            "DELETE FROM favorites WHERE user_id = ?1 AND id = ?2",
+            params![user_id.0 as i64, id],
+        )?;
+        Ok(removed > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::naming::export::PartRecord;
+    use crate::naming::format::PartKind;
+
+    fn record(full_name: &str) -> NameRecord {
+        NameRecord {
+            role: None,
+            full_name: full_name.into(),
+            usage: Some("ita".into()),
+            given_usage: Some("iri".into()),
+            parts: vec![PartRecord {
+                kind: PartKind::Given,
+                label: "Given name".into(),
+                text: full_name.split_whitespace().next().unwrap().into(),
+                url: Some("https://www.behindthename.com/name/siobhan".into()),
+            }],
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn round_trips_favorites() {
+        let storage = Storage::open(":memory:").unwrap();
+        let (user_id, other_user_id) = (UserId(10), UserId(11));
+        assert!(storage
+            .add_favorite(user_id, &record("Siobhan Rossi"), 100)
+            .unwrap());
+        assert!(!storage
+            .add_favorite(user_id, &record("Siobhan Rossi"), 200)
+            .unwrap());
+        assert!(storage
+            .add_favorite(user_id, &record("Ada Lovelace"), 300)
+            .unwrap());
+        assert!(storage
+            .add_favorite(other_user_id, &record("Siobhan Rossi"), 400)
+            .unwrap());
+
+        let favorites = storage.favorites(user_id).unwrap();
+        let names = favorites
+            .iter()
+            .map(|favorite| favorite.record.full_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Siobhan Rossi", "Ada Lovelace"]);
+        let first = &favorites[0];
+        assert_eq!(first.created_at, 100);
+        assert_eq!(first.record.usage.as_deref(), Some("ita"));
+        assert_eq!(first.record.given_usage.as_deref(), Some("iri"));
+        assert_eq!(first.record.parts[0].text, "Siobhan");
+        assert_eq!(
+            first.record.parts[0].url.as_deref(),
+            Some("https://www.behindthename.com/name/siobhan")
+        );
+        assert_eq!(storage.favorite_count(user_id).unwrap(), 2);
+
+        assert!(!storage.remove_favorite(other_user_id, first.id).unwrap());
+        assert!(storage.remove_favorite(user_id, first.id).unwrap());
+        assert_eq!(storage.favorite_count(user_id).unwrap(), 1);
+        assert_eq!(storage.favorite_count(other_user_id).unwrap(), 1);
+    }
+}
@@ -0,0 +1,306 @@
+use poise::serenity_prelude::{GuildId, UserId};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Row};
+
+use crate::storage::Storage;
+
+/// A generated name, as remembered in the history.
+pub(crate) struct HistoryEntry {
+    pub(crate) guild_id: Option<GuildId>,
+    pub(crate) user_id: UserId,
+    pub(crate) first_name: String,
+    pub(crate) last_name: Option<String>,
+    pub(crate) full_name: String,
+    /// The usage code of the name, or of its surname for mixed names.
+    pub(crate) usage: Option<String>,
+    pub(crate) given_usage: Option<String>,
+    pub(crate) mode: String,
+    /// The gender asked for, as a BehindTheName code; empty for any.
+    pub(crate) gender: String,
+    pub(crate) created_at: i64,
+}
+
+/// Which history entries to list. Unset fields match everything.
+#[derive(Default)]
+pub(crate) struct HistoryFilter {
+    pub(crate) guild_id: Option<GuildId>,
+    pub(crate) user_id: Option<UserId>,
+    pub(crate) mode: Option<String>,
+    /// Matches either the given name's or the surname's usage.
+    pub(crate) usage: Option<String>,
+    pub(crate) gender: Option<String>,
+    /// Matches part of the full name, ignoring case.
+    pub(crate) name: Option<String>,
+    /// Only entries created since then, in Unix seconds.
+    pub(crate) since: Option<i64>,
+}
+
+/// Escape LIKE's wildcards and the escape character itself, so that they
+/// match literally.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+impl HistoryFilter {
+    fn where_clause(&self) -> (String, Vec<Value>) {
+        let mut conditions = vec![];
+        let mut values = vec![];
+        let mut condition = |sql: &str, value: Value| {
+            values.push(value);
+            conditions.push(sql.replace('?', &format!("?{}", values.len())));
+        };
+
+        if let Some(guild_id) = self.guild_id {
+            condition("guild_id = ?", Value::Integer(guild_id.0 as i64));
+        }
+        if let Some(user_id) = self.user_id {
+            condition("user_id = ?", Value::Integer(user_id.0 as i64));
+        }
+        if let Some(mode) = &self.mode {
+            condition("mode = ?", Value::Text(mode.clone()));
+        }
+        if let Some(usage) = &self.usage {
+            condition("(usage = ? OR given_usage = ?)", Value::Text(usage.clone()));
+        }
+        if let Some(gender) = &self.gender {
+            condition("gender = ?", Value::Text(gender.clone()));
+        }
+        if let Some(name) = &self.name {
+            condition(
+                "full_name LIKE ? ESCAPE '\\'",
+                Value::Text(format!("%{}%", escape_like(name))),
+            );
+        }
+        if let Some(since) = self.since {
+            condition("created_at >= ?", Value::Integer(since));
+        }
+
+        if conditions.is_empty() {
+            (String::new(), values)
+        } else {
+            (format!("WHERE {}", conditions.join(" AND ")), values)
+        }
+    }
+}
+
+fn history_entry(row: &Row<'_>) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        guild_id: row.get::<_, Option<i64>>(0)?.map(|id| GuildId(id as u64)),
+        user_id: UserId(row.get::<_, i64>(1)? as u64),
+        first_name: row.get(2)?,
+        last_name: row.get(3)?,
+        full_name: row.get(4)?,
+        usage: row.get(5)?,
+        given_usage: row.get(6)?,
+        mode: row.get(7)?,
+        gender: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+impl Storage {
+    pub(crate) fn add_history(&self, entry: &HistoryEntry) -> rusqlite::Result<()> {
+        self.connection().execute(
+            "INSERT INTO name_history
+                (guild_id, user_id, first_name, last_name, full_name, usage, given_usage, mode, gender, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                entry.guild_id.map(|id| id.0 as i64),
+                entry.user_id.0 as i64,
+                entry.first_name,
+                entry.last_name,
+                entry.full_name,
+                entry.usage,
+                entry.given_usage,
+                entry.mode,
+                entry.gender,
+                entry.created_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The most recent history entries matching a filter, newest first.
+    pub(crate) fn history(
+        &self,
+        filter: &HistoryFilter,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<HistoryEntry>> {
+        let (where_clause, mut values) = filter.where_clause();
+        values.push(Value::Integer(limit as i64));
+
+        let connection = self.connection();
+        let mut statement = connection.prepare(&format!(
+            "SELECT guild_id, user_id, first_name, last_name, full_name, usage, given_usage, mode, gender, created_at
+             FROM name_history {} ORDER BY created_at DESC, id DESC LIMIT ?{}",
+            where_clause,
+            values.len()
+        ))?;
+        let entries = statement
+            .query_map(params_from_iter(values), history_entry)?
+            .collect();
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        guild_id: Option<u64>,
+        user_id: u64,
+        full_name: &str,
+        created_at: i64,
+    ) -> HistoryEntry {
+        let (first_name, last_name) = match full_name.split_once(' ') {
+            Some((first_name, last_name)) => (first_name, Some(last_name.to_string())),
+            None => (full_name, None),
+        };
+        HistoryEntry {
+            guild_id: guild_id.map(GuildId),
+            user_id: UserId(user_id),
+            first_name: first_name.into(),
+            last_name,
+            full_name: full_name.into(),
+            usage: Some("eng".into()),
+            given_usage: None,
+            mode: "coherent".into(),
+            gender: String::new(),
+            created_at,
+        }
+    }
+
+    fn storage() -> Storage {
+        let storage = Storage::open(":memory:").unwrap();
+        for entry in [
+            entry(Some(1), 10, "Ada Lovelace", 100),
+            HistoryEntry {
+                usage: Some("ita".into()),
+                given_usage: Some("iri".into()),
+                mode: "mixed".into(),
+                gender: "f".into(),
+                ..entry(Some(1), 11, "Siobhan Rossi", 200)
+            },
+            entry(Some(2), 10, "Alan Turing", 300),
+            entry(None, 10, "100% Real_Name", 400),
+            entry(None, 11, "1000 Rea\\l Name", 500),
+        ] {
+            storage.add_history(&entry).unwrap();
+        }
+        storage
+    }
+
+    fn names(storage: &Storage, filter: HistoryFilter, limit: usize) -> Vec<String> {
+        storage
+            .history(&filter, limit)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.full_name)
+            .collect()
+    }
+
+    #[test]
+    fn lists_entries_newest_first() {
+        let storage = storage();
+        assert_eq!(
+            names(&storage, HistoryFilter::default(), 3),
+            ["1000 Rea\\l Name", "100% Real_Name", "Alan Turing"]
+        );
+        let entries = storage.history(&HistoryFilter::default(), 10).unwrap();
+        let siobhan = entries
+            .iter()
+            .find(|entry| entry.first_name == "Siobhan")
+            .unwrap();
+        assert_eq!(siobhan.guild_id, Some(GuildId(1)));
+        assert_eq!(siobhan.last_name.as_deref(), Some("Rossi"));
+        assert_eq!(siobhan.given_usage.as_deref(), Some("iri"));
+        assert_eq!(
+            (siobhan.mode.as_str(), siobhan.gender.as_str()),
+            ("mixed", "f")
+        );
+    }
+
+    #[test]
+    fn filters_entries() {
+        let storage = storage();
+        for (filter, expected) in [
+            (
+                HistoryFilter {
+                    guild_id: Some(GuildId(1)),
+                    ..Default::default()
+                },
+                vec!["Siobhan Rossi", "Ada Lovelace"],
+            ),
+            (
+                HistoryFilter {
+                    user_id: Some(UserId(10)),
+                    guild_id: Some(GuildId(1)),
+                    ..Default::default()
+                },
+                vec!["Ada Lovelace"],
+            ),
+            (
+                HistoryFilter {
+                    mode: Some("mixed".into()),
+                    ..Default::default()
+                },
+                vec!["Siobhan Rossi"],
+            ),
+            (
+                HistoryFilter {
+                    usage: Some("iri".into()),
+                    ..Default::default()
+                },
+                vec!["Siobhan Rossi"],
+            ),
+            (
+                HistoryFilter {
+                    gender: Some("f".into()),
+                    ..Default::default()
+                },
+                vec!["Siobhan Rossi"],
+            ),
+            (
+                HistoryFilter {
+                    since: Some(300),
+                    user_id: Some(UserId(10)),
+                    ..Default::default()
+                },
+                vec!["100% Real_Name", "Alan Turing"],
+            ),
+            (
+                HistoryFilter {
+                    name: Some("LOVE".into()),
+                    ..Default::default()
+                },
+                vec!["Ada Lovelace"],
+            ),
+        ] {
+            assert_eq!(names(&storage, filter, 10), expected);
+        }
+    }
+
+    #[test]
+    fn matches_wildcards_in_names_literally() {
+        let storage = storage();
+        let named = |name: &str| {
+            names(
+                &storage,
+                HistoryFilter {
+                    name: Some(name.into()),
+                    ..Default::default()
+                },
+                10,
+            )
+        };
+        assert_eq!(named("0%"), ["100% Real_Name"]);
+        assert_eq!(named("l_n"), ["100% Real_Name"]);
+        assert_eq!(named("%"), ["100% Real_Name"]);
+        assert_eq!(named("_"), ["100% Real_Name"]);
+        assert_eq!(named("a\\l"), ["1000 Rea\\l Name"]);
+        assert!(named("Ad_").is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::Connection;
+
+pub(crate) mod daily;
+pub(crate) mod favorites;
+pub(crate) mod history;
+pub(crate) mod no_repeat;
+pub(crate) mod prefs;
+pub(crate) mod rosters;
+pub(crate) mod roulette;
+pub(crate) mod settings;
+
+/// Tables are created when missing, so new tables can be added here without
+/// touching existing databases.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS roulettes (
+    guild_id INTEGER PRIMARY KEY,
+    channel_id INTEGER NOT NULL,
+    ends_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS roulette_nicknames (
+    guild_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    original_nickname TEXT,
+    PRIMARY KEY (guild_id, user_id)
+);
+CREATE TABLE IF NOT EXISTS daily_names (
+    guild_id INTEGER PRIMARY KEY,
+    channel_id INTEGER NOT NULL,
+    time TEXT NOT NULL,
+    time_zone TEXT NOT NULL,
+    no_repeat_days INTEGER NOT NULL,
+    last_posted TEXT
+);
+CREATE TABLE IF NOT EXISTS daily_name_posts (
+    guild_id INTEGER NOT NULL,
+    first_name TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    posted_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS name_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id INTEGER,
+    user_id INTEGER NOT NULL,
+    first_name TEXT NOT NULL,
+    last_name TEXT,
+    full_name TEXT NOT NULL,
+    usage TEXT,
+    given_usage TEXT,
+    mode TEXT NOT NULL,
+    gender TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS name_history_guild ON name_history (guild_id, created_at);
+CREATE INDEX IF NOT EXISTS name_history_user ON name_history (user_id, created_at);
+CREATE TABLE IF NOT EXISTS favorites (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    full_name TEXT NOT NULL,
+    record TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    UNIQUE (user_id, full_name)
+);
+CREATE TABLE IF NOT EXISTS rosters (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    created_by INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    UNIQUE (guild_id, name COLLATE NOCASE)
+);
+CREATE TABLE IF NOT EXISTS roster_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    roster_id INTEGER NOT NULL,
+    first_name TEXT NOT NULL,
+    full_name TEXT NOT NULL,
+    record TEXT NOT NULL,
+    notes TEXT,
+    added_by INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    UNIQUE (roster_id, full_name)
+);
+CREATE TABLE IF NOT EXISTS guild_settings (
+    guild_id INTEGER PRIMARY KEY,
+    gender TEXT,
+    mode TEXT,
+    usages TEXT,
+    exclusions TEXT NOT NULL,
+    ephemeral INTEGER NOT NULL,
+    prefix TEXT,
+    channels TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS user_prefs (
+    user_id INTEGER PRIMARY KEY,
+    gender TEXT,
+    mode TEXT,
+    usages TEXT,
+    western_order INTEGER,
+    surname_conjunction INTEGER
+);
+CREATE TABLE IF NOT EXISTS no_repeat (
+    guild_id INTEGER PRIMARY KEY,
+    names INTEGER,
+    days INTEGER,
+    full_names INTEGER NOT NULL
+);
+";
+
+/// The bot's persistent state, kept in an SQLite database.
+pub(crate) struct Storage {
+    connection: Mutex<Connection>,
+}
+
+impl Storage {
+    pub(crate) fn open(path: &str) -> rusqlite::Result<Storage> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Storage {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap()
+    }
+}
+
+/// The current time, in seconds since the Unix epoch.
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
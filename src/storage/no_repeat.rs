@@ -0,0 +1,62 @@
+use poise::serenity_prelude::GuildId;
+use rusqlite::{params, OptionalExtension};
+
+use crate::storage::Storage;
+
+/// How far back a guild's generated names can't be repeated.
+pub(crate) struct NoRepeat {
+    /// The last this many names generated in the guild.
+    pub(crate) names: Option<u32>,
+    /// Names generated in the guild within this many days.
+    pub(crate) days: Option<u32>,
+    /// Compare full names rather than first names.
+    pub(crate) full_names: bool,
+}
+
+impl Storage {
+    pub(crate) fn set_no_repeat(
+        &self,
+        guild_id: GuildId,
+        no_repeat: &NoRepeat,
+    ) -> rusqlite::Result<()> {
+        self.connection().execute(
+            "INSERT INTO no_repeat (guild_id, names, days, full_names)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (guild_id) DO UPDATE SET
+                names = excluded.names,
+                days = excluded.days,
+                full_names = excluded.full_names",
+            params![
+                guild_id.0 as i64,
+                no_repeat.names,
+                no_repeat.days,
+                no_repeat.full_names
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn no_repeat(&self, guild_id: GuildId) -> rusqlite::Result<Option<NoRepeat>> {
+        self.connection()
+            .query_row(
+                "SELECT names, days, full_names FROM no_repeat WHERE guild_id = ?1",
+                params![guild_id.0 as i64],
+                |row| {
+                    Ok(NoRepeat {
+                        names: row.get(0)?,
+                        days: row.get(1)?,
+                        full_names: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub(crate) fn remove_no_repeat(&self, guild_id: GuildId) -> rusqlite::Result<bool> {
+        let removed = self.connection().execute(
+            "DELETE FROM no_repeat WHERE guild_id = ?1",
+            params![guild_id.0 as i64],
+        )?;
+        Ok(removed > 0)
+    }
+}
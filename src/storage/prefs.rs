@@ -0,0 +1,96 @@
+use std::str::FromStr;
+
+use poise::serenity_prelude::UserId;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::resources::types::NameDefaults;
+use crate::storage::Storage;
+
+/// A user's own defaults, set with /rnc_prefs.
+#[derive(Clone, Default)]
+pub(crate) struct UserPrefs {
+    pub(crate) defaults: NameDefaults,
+    pub(crate) western_order: Option<bool>,
+    pub(crate) surname_conjunction: Option<bool>,
+}
+
+/// Parse an optional stored value, forgetting it if it no longer parses.
+fn parse<T: FromStr>(text: Option<String>) -> Option<T> {
+    text.and_then(|text| text.parse().ok())
+}
+
+fn read(connection: &Connection, user_id: UserId) -> rusqlite::Result<UserPrefs> {
+    let prefs = connection
+        .query_row(
+            "SELECT gender, mode, usages, western_order, surname_conjunction
+             FROM user_prefs WHERE user_id = ?1",
+            params![user_id.0 as i64],
+            |row| {
+                Ok(UserPrefs {
+                    defaults: NameDefaults {
+                        gender: parse(row.get(0)?),
+                        mode: parse(row.get(1)?),
+                        usages: parse(row.get(2)?),
+                    },
+                    western_order: row.get(3)?,
+                    surname_conjunction: row.get(4)?,
+                })
+            },
+        )
+        .optional()?;
+    Ok(prefs.unwrap_or_default())
+}
+
+fn write(connection: &Connection, user_id: UserId, prefs: &UserPrefs) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT INTO user_prefs (user_id, gender, mode, usages, western_order, surname_conjunction)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (user_id) DO UPDATE SET
+            gender = excluded.gender,
+            mode = excluded.mode,
+            usages = excluded.usages,
+            western_order = excluded.western_order,
+            surname_conjunction = excluded.surname_conjunction",
+        params![
+            user_id.0 as i64,
+            prefs.defaults.gender.map(|gender| gender.to_string()),
+            prefs.defaults.mode.map(|mode| mode.to_string()),
+            prefs
+                .defaults
+                .usages
+                .as_ref()
+                .map(|usages| usages.to_string()),
+            prefs.western_order,
+            prefs.surname_conjunction
+        ],
+    )?;
+    Ok(())
+}
+
+impl Storage {
+    /// A user's preferences, or none if they have none.
+    pub(crate) fn user_prefs(&self, user_id: UserId) -> rusqlite::Result<UserPrefs> {
+        read(&self.connection(), user_id)
+    }
+
+    /// Change a user's preferences, returning the new preferences.
+    pub(crate) fn update_user_prefs(
+        &self,
+        user_id: UserId,
+        update: impl FnOnce(&mut UserPrefs),
+    ) -> rusqlite::Result<UserPrefs> {
+        let connection = self.connection();
+        let mut prefs = read(&connection, user_id)?;
+        update(&mut prefs);
+        write(&connection, user_id, &prefs)?;
+        Ok(prefs)
+    }
+
+    pub(crate) fn remove_user_prefs(&self, user_id: UserId) -> rusqlite::Result<()> {
+        self.connection().execute(
+            "DELETE FROM user_prefs WHERE user_id = ?1",
+            params![user_id.0 as i64],
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+use poise::serenity_prelude::{GuildId, UserId};
+use rusqlite::types::Type;
+use rusqlite::{params, OptionalExtension, Row};
+
+use crate::naming::export::NameRecord;
+use crate::storage::Storage;
+
+/// A named list of names shared by a guild, e.g. a campaign's NPCs.
+pub(crate) struct Roster {
+    pub(crate) id: i64,
+    pub(crate) name: String,
+    pub(crate) created_by: UserId,
+}
+
+pub(crate) struct RosterEntry {
+    pub(crate) id: i64,
+    /// The name with its parts' BehindTheName links and usages, and notes.
+    pub(crate) record: NameRecord,
+}
+
+fn roster(row: &Row<'_>) -> rusqlite::Result<Roster> {
+    Ok(Roster {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        created_by: UserId(row.get::<_, i64>(2)? as u64),
+    })
+}
+
+fn roster_entry(row: &Row<'_>) -> rusqlite::Result<RosterEntry> {
+    let record: String = row.get(1)?;
+    let mut record: NameRecord = serde_json::from_str(&record)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, Type::Text, Box::new(e)))?;
+    record.notes = row.get(2)?;
+    Ok(RosterEntry {
+        id: row.get(0)?,
+        record,
+    })
+}
+
+impl Storage {
+    /// Create a roster, returning false if the guild already has one by
+    /// that name, ignoring case.
+    pub(crate) fn create_roster(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        created_by: UserId,
+        created_at: i64,
+    ) -> rusqlite::Result<bool> {
+        let created = self.connection().execute(
+            "INSERT OR IGNORE INTO rosters (guild_id, name, created_by, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![guild_id.0 as i64, name, created_by.0 as i64, created_at],
+        )?;
+        Ok(created > 0)
+    }
+
+    pub(crate) fn roster(&self, guild_id: GuildId, name: &str) -> rusqlite::Result<Option<Roster>> {
+        self.connection()
+            .query_row(
+                "SELECT id, name, created_by FROM rosters
+                 WHERE guild_id = ?1 AND name = ?2 COLLATE NOCASE",
+                params![guild_id.0 as i64, name],
+                roster,
+            )
+            .optional()
+    }
+
+    /// A guild's rosters by name, with how many entries each has.
+    pub(crate) fn rosters(&self, guild_id: GuildId) -> rusqlite::Result<Vec<(Roster, usize)>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT rosters.id, name, created_by, COUNT(roster_entries.id)
+             FROM rosters LEFT JOIN roster_entries ON roster_entries.roster_id = rosters.id
+             WHERE guild_id = ?1
+             GROUP BY rosters.id
+             ORDER BY name COLLATE NOCASE",
+        )?;
+        let rosters = statement
+            .query_map(params![guild_id.0 as i64], |row| {
+                Ok((roster(row)?, row.get(3)?))
+            })?
+            .collect();
+        rosters
+    }
+
+    pub(crate) fn delete_roster(&self, roster_id: i64) -> rusqlite::Result<()> {
+        let connection = self.connection();
+        connection.execute(
+            "DELETE FROM roster_entries WHERE roster_id = ?1",
+            params![roster_id],
+        )?;
+        connection.execute("DELETE FROM rosters WHERE id = ?1", params![roster_id])?;
+        Ok(())
+    }
+
+    /// Add a name to a roster, returning false if it's already on it.
+    pub(crate) fn add_roster_entry(
+        &self,
+        roster_id: i64,
+        first_name: &str,
+        record: &NameRecord,
+        added_by: UserId,
+        created_at: i64,
+    ) -> rusqlite::Result<bool> {
+        let record_json = serde_json::to_string(&NameRecord {
+            notes: None,
+            ..record.clone()
+        })
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let added = self.connection().execute(
+            "INSERT OR IGNORE INTO roster_entries
+                (roster_id, first_name, full_name, record, notes, added_by, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                roster_id,
+                first_name,
+                record.full_name,
+                record_json,
+                record.notes,
+                added_by.0 as i64,
+                created_at
+            ],
+        )?;
+        Ok(added > 0)
+    }
+
+    /// A roster's entries, in the order they were added.
+    pub(crate) fn roster_entries(&self, roster_id: i64) -> rusqlite::Result<Vec<RosterEntry>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT id, record, notes FROM roster_entries
+             WHERE roster_id = ?1 ORDER BY id",
+        )?;
+        let entries = statement
+            .query_map(params![roster_id], roster_entry)?
+            .collect();
+        entries
+    }
+
+    pub(crate) fn roster_entry_count(&self, roster_id: i64) -> rusqlite::Result<usize> {
+        self.connection().query_row(
+            "SELECT COUNT(*) FROM roster_entries WHERE roster_id = ?1",
+            params![roster_id],
+            |row| row.get(0),
+        )
+    }
+
+    /// The first names on a roster.
+    pub(crate) fn roster_first_names(&self, roster_id: i64) -> rusqlite::Result<Vec<String>> {
+        let connection = self.connection();
+        let mut statement =
+            connection.prepare("SELECT first_name FROM roster_entries WHERE roster_id = ?1")?;
+        let first_names = statement
+            .query_map(params![roster_id], |row| row.get(0))?
+            .collect();
+        first_names
+    }
+
+    pub(crate) fn remove_roster_entry(&self, roster_id: i64, id: i64) -> rusqlite::Result<bool> {
+        let removed = self.connection().execute(
+            "DELETE FROM roster_entries WHERE roster_id = ?1 AND id = ?2",
+            params![roster_id, id],
+        )?;
+        Ok(removed > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::naming::export::PartRecord;
+    use crate::naming::format::PartKind;
+
+    fn record(first_name: &str, last_name: &str, notes: Option<&str>) -> NameRecord {
+        NameRecord {
+            role: None,
+            full_name: format!("{} {}", first_name, last_name),
+            usage: Some("eng".into()),
+            given_usage: None,
+            parts: vec![
+                PartRecord {
+                    kind: PartKind::Given,
+                    label: "Given name".into(),
+                    text: first_name.into(),
+                    url: Some(format!("https://www.behindthename.com/name/{}", first_name)),
+                },
+                PartRecord {
+                    kind: PartKind::Family,
+                    label: "Surname".into(),
+                    text: last_name.into(),
+                    url: None,
+                },
+            ],
+            notes: notes.map(String::from),
+        }
+    }
+
+    #[test]
+    fn round_trips_rosters_and_entries() {
+        let storage = Storage::open(":memory:").unwrap();
+        let (guild_id, user_id) = (GuildId(1), UserId(10));
+        assert!(storage
+            .create_roster(guild_id, "NPCs", user_id, 100)
+            .unwrap());
+        assert!(!storage
+            .create_roster(guild_id, "npcs", user_id, 200)
+            .unwrap());
+        assert!(storage
+            .create_roster(GuildId(2), "NPCs", user_id, 200)
+            .unwrap());
+
+        let roster = storage.roster(guild_id, "nPcS").unwrap().unwrap();
+        assert_eq!((roster.name.as_str(), roster.created_by), ("NPCs", user_id));
+        assert!(storage.roster(guild_id, "Villains").unwrap().is_none());
+
+        let ada = record("Ada", "Lovelace", Some("The innkeeper"));
+        assert!(storage
+            .add_roster_entry(roster.id, "Ada", &ada, user_id, 100)
+            .unwrap());
+        assert!(!storage
+            .add_roster_entry(roster.id, "Ada", &ada, user_id, 200)
+            .unwrap());
+        let alan = record("Alan", "Turing", None);
+        assert!(storage
+            .add_roster_entry(roster.id, "Alan", &alan, user_id, 300)
+            .unwrap());
+
+        let entries = storage.roster_entries(roster.id).unwrap();
+        let names = entries
+            .iter()
+            .map(|entry| entry.record.full_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Ada Lovelace", "Alan Turing"]);
+        let first = &entries[0].record;
+        assert_eq!(first.notes.as_deref(), Some("The innkeeper"));
+        assert_eq!(first.usage.as_deref(), Some("eng"));
+        assert_eq!(first.parts.len(), 2);
+        assert_eq!(first.parts[0].kind, PartKind::Given);
+        assert_eq!(first.parts[0].url, ada.parts[0].url);
+        assert_eq!(first.parts[1].text, "Lovelace");
+
+        assert_eq!(storage.roster_entry_count(roster.id).unwrap(), 2);
+        assert_eq!(
+            storage.roster_first_names(roster.id).unwrap(),
+            ["Ada", "Alan"]
+        );
+        let rosters = storage.rosters(guild_id).unwrap();
+        assert_eq!(rosters.len(), 1);
+        assert_eq!(rosters[0].1, 2);
+
+        assert!(storage
+            .remove_roster_entry(roster.id, entries[0].id)
+            .unwrap());
+        assert!(!storage
+            .remove_roster_entry(roster.id, entries[0].id)
+            .unwrap());
+        assert_eq!(storage.roster_first_names(roster.id).unwrap(), ["Alan"]);
+
+        storage.delete_roster(roster.id).unwrap();
+        assert!(storage.roster(guild_id, "NPCs").unwrap().is_none());
+        assert!(storage.roster_entries(roster.id).unwrap().is_empty());
+        assert!(storage.roster(GuildId(2), "NPCs").unwrap().is_some());
+    }
+}
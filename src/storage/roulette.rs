@@ -0,0 +1,122 @@
+use poise::serenity_prelude::{ChannelId, GuildId, UserId};
+use rusqlite::{params, OptionalExtension};
+
+use crate::storage::Storage;
+
+/// A nickname roulette in progress.
+#[derive(Clone)]
+pub(crate) struct Roulette {
+    pub(crate) guild_id: GuildId,
+    pub(crate) channel_id: ChannelId,
+    /// When to restore the original nicknames, in Unix seconds.
+    pub(crate) ends_at: i64,
+}
+
+impl Storage {
+    /// Record a new roulette, unless one is already running in its guild.
+    pub(crate) fn start_roulette(&self, roulette: &Roulette) -> rusqlite::Result<bool> {
+        let inserted = self.connection().execute(
+            "INSERT OR IGNORE INTO roulettes (guild_id, channel_id, ends_at) VALUES (?1, ?2, ?3)",
+            params![
+                roulette.guild_id.0 as i64,
+                roulette.channel_id.0 as i64,
+                roulette.ends_at
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub(crate) fn roulette(&self, guild_id: GuildId) -> rusqlite::Result<Option<Roulette>> {
+        self.connection()
+            .query_row(
+                "SELECT channel_id, ends_at FROM roulettes WHERE guild_id = ?1",
+                params![guild_id.0 as i64],
+                |row| {
+                    Ok(Roulette {
+                        guild_id,
+                        channel_id: ChannelId(row.get::<_, i64>(0)? as u64),
+                        ends_at: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub(crate) fn roulettes(&self) -> rusqlite::Result<Vec<Roulette>> {
+        let connection = self.connection();
+        let mut statement =
+            connection.prepare("SELECT guild_id, channel_id, ends_at FROM roulettes")?;
+        let roulettes = statement
+            .query_map([], |row| {
+                Ok(Roulette {
+                    guild_id: GuildId(row.get::<_, i64>(0)? as u64),
+                    channel_id: ChannelId(row.get::<_, i64>(1)? as u64),
+                    ends_at: row.get(2)?,
+                })
+            })?
+            .collect();
+        roulettes
+    }
+
+    /// Remember a participant's nickname from before the roulette.
+    /// Remember a participant's original nickname, if the roulette is still
+    /// running. Returns whether it is.
+    pub(crate) fn add_roulette_nickname(
+        &self,
+        roulette: &Roulette,
+        user_id: UserId,
+        original_nickname: Option<&str>,
+    ) -> rusqlite::Result<bool> {
+        let connection = self.connection();
+        connection.execute(
+            "INSERT OR IGNORE INTO roulette_nicknames (guild_id, user_id, original_nickname)
+             SELECT ?1, ?2, ?3
+             WHERE EXISTS (SELECT 1 FROM roulettes WHERE guild_id = ?1 AND ends_at = ?4)",
+            params![
+                roulette.guild_id.0 as i64,
+                user_id.0 as i64,
+                original_nickname,
+                roulette.ends_at
+            ],
+        )?;
+        drop(connection);
+        self.roulette_running(roulette)
+    }
+
+    /// Whether this roulette is still running, rather than ended or
+    /// replaced by another.
+    pub(crate) fn roulette_running(&self, roulette: &Roulette) -> rusqlite::Result<bool> {
+        Ok(self
+            .roulette(roulette.guild_id)?
+            .is_some_and(|current| current.ends_at == roulette.ends_at))
+    }
+
+    pub(crate) fn roulette_nicknames(
+        &self,
+        guild_id: GuildId,
+    ) -> rusqlite::Result<Vec<(UserId, Option<String>)>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT user_id, original_nickname FROM roulette_nicknames WHERE guild_id = ?1",
+        )?;
+        let nicknames = statement
+            .query_map(params![guild_id.0 as i64], |row| {
+                Ok((UserId(row.get::<_, i64>(0)? as u64), row.get(1)?))
+            })?
+            .collect();
+        nicknames
+    }
+
+    pub(crate) fn end_roulette(&self, guild_id: GuildId) -> rusqlite::Result<()> {
+        let connection = self.connection();
+        connection.execute(
+            "DELETE FROM roulette_nicknames WHERE guild_id = ?1",
+            params![guild_id.0 as i64],
+        )?;
+        connection.execute(
+            "DELETE FROM roulettes WHERE guild_id = ?1",
+            params![guild_id.0 as i64],
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use std::str::FromStr;
+
+use poise::serenity_prelude::{ChannelId, GuildId};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::naming::filter::Exclusions;
+use crate::resources::types::NameDefaults;
+use crate::storage::Storage;
+
+/// A guild's defaults and restrictions, set with /rnc_settings.
+#[derive(Clone, Default)]
+pub(crate) struct GuildSettings {
+    pub(crate) defaults: NameDefaults,
+    pub(crate) exclusions: Exclusions,
+    /// Answer application commands with messages only the user sees.
+    pub(crate) ephemeral: bool,
+    pub(crate) prefix: Option<String>,
+    /// The channels commands may be used in; any channel if empty.
+    pub(crate) channels: Vec<ChannelId>,
+}
+
+/// Parse an optional stored value, forgetting it if it no longer parses.
+fn parse<T: FromStr>(text: Option<String>) -> Option<T> {
+    text.and_then(|text| text.parse().ok())
+}
+
+fn read(connection: &Connection, guild_id: GuildId) -> rusqlite::Result<GuildSettings> {
+    let settings = connection
+        .query_row(
+            "SELECT gender, mode, usages, exclusions, ephemeral, prefix, channels
+             FROM guild_settings WHERE guild_id = ?1",
+            params![guild_id.0 as i64],
+            |row| {
+                let channels: String = row.get(6)?;
+                Ok(GuildSettings {
+                    defaults: NameDefaults {
+                        gender: parse(row.get(0)?),
+                        mode: parse(row.get(1)?),
+                        usages: parse(row.get(2)?),
+                    },
+                    exclusions: parse(row.get(3)?).unwrap_or_default(),
+                    ephemeral: row.get(4)?,
+                    prefix: row.get(5)?,
+                    channels: channels
+                        .split_whitespace()
+                        .filter_map(|id| id.parse().ok().map(ChannelId))
+                        .collect(),
+                })
+            },
+        )
+        .optional()?;
+    Ok(settings.unwrap_or_default())
+}
+
+fn write(
+    connection: &Connection,
+    guild_id: GuildId,
+    settings: &GuildSettings,
+) -> rusqlite::Result<()> {
+    let channels = settings
+        .channels
+        .iter()
+        .map(|id| id.0.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    connection.execute(
+        "INSERT INTO guild_settings (guild_id, gender, mode, usages, exclusions, ephemeral, prefix, channels)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT (guild_id) DO UPDATE SET
+            gender = excluded.gender,
+            mode = excluded.mode,
+            usages = excluded.usages,
+            exclusions = excluded.exclusions,
+            ephemeral = excluded.ephemeral,
+            prefix = excluded.prefix,
+            channels = excluded.channels",
+        params![
+            guild_id.0 as i64,
+            settings.defaults.gender.map(|gender| gender.to_string()),
+            settings.defaults.mode.map(|mode| mode.to_string()),
+            settings
+                .defaults
+                .usages
+                .as_ref()
+                .map(|usages| usages.to_string()),
+            settings.exclusions.to_string(),
+            settings.ephemeral,
+            settings.prefix,
+            channels
+        ],
+    )?;
+    Ok(())
+}
+
+impl Storage {
+    /// A guild's settings, or the defaults if it has none.
+    pub(crate) fn guild_settings(&self, guild_id: GuildId) -> rusqlite::Result<GuildSettings> {
+        read(&self.connection(), guild_id)
+    }
+
+    /// Change a guild's settings, returning the new settings.
+    pub(crate) fn update_guild_settings(
+        &self,
+        guild_id: GuildId,
+        update: impl FnOnce(&mut GuildSettings),
+    ) -> rusqlite::Result<GuildSettings> {
+        let connection = self.connection();
+        let mut settings = read(&connection, guild_id)?;
+        update(&mut settings);
+        write(&connection, guild_id, &settings)?;
+        Ok(settings)
+    }
+
+    pub(crate) fn remove_guild_settings(&self, guild_id: GuildId) -> rusqlite::Result<()> {
+        self.connection().execute(
+            "DELETE FROM guild_settings WHERE guild_id = ?1",
+            params![guild_id.0 as i64],
+        )?;
+        Ok(())
+    }
+}